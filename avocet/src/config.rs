@@ -10,6 +10,16 @@ impl BuildMode {
     pub const fn equals(self, rhs: BuildMode) -> bool { self as u8 == rhs as u8 }
 }
 
+impl std::fmt::Display for BuildMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BuildMode::Debug => "debug",
+            BuildMode::Release => "release",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[cfg(debug_assertions)]
 const BUILD_MODE: BuildMode = BuildMode::Debug;
 
@@ -40,6 +50,17 @@ impl TargetPlatform {
     pub const fn equals(self, rhs: TargetPlatform) -> bool { self as u8 == rhs as u8 }
 }
 
+impl std::fmt::Display for TargetPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            TargetPlatform::Windows => "Windows",
+            TargetPlatform::Mac => "Mac",
+            TargetPlatform::Linux => "Linux",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[cfg(target_os = "windows")]
 const TARGET_PLATFORM: TargetPlatform = TargetPlatform::Windows;
 
@@ -1,12 +1,48 @@
 use crate::{
-    graphics::{VAOResource, VBOResource},
-    validation::gl_function,
+    graphics::{check_array_buffer_bound, BufferUsage, ShaderProgram, VAOResource, VBOResource},
+    math::Mat4,
+    validation::{self, gl_function},
+    version,
 };
 
+use std::io::{Error, ErrorKind, Result};
+
+use gl::types::GLenum;
+
+/// Anything which issues its own draw calls, so different kinds of geometry can be drawn from
+/// a single list, e.g. 'Vec<Box<dyn Drawable>>'
+///
+/// Draws with whichever program is bound. Closures are drawables too, typically binding
+/// a program before drawing: 'move || { program.bind(); mesh.draw(); }'
+pub trait Drawable {
+    fn draw(&self);
+}
+
+impl<F: Fn()> Drawable for F {
+    fn draw(&self) { self() }
+}
+
+/// Geometry with a model matrix placing it in the scene, the identity by default
+pub trait Transformable: Drawable {
+    fn transform(&self) -> &Mat4;
+    fn set_transform(&mut self, transform: Mat4);
+
+    /// Binds the program and uploads the model matrix to its named 'mat4' uniform, then draws
+    fn draw_with(&self, program: &ShaderProgram, uniform_name: &str) {
+        program.bind();
+        program.set_uniform_mat4(uniform_name, &self.transform().columns);
+        self.draw();
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+
 #[allow(dead_code)]
 pub struct Triangle {
     vertex_array_object: VAOResource,
     vertex_buffer_object: VBOResource,
+    vertex_count: usize,
+    transform: Mat4,
 }
 
 impl Triangle {
@@ -16,22 +52,489 @@ impl Triangle {
         0.0,  0.5, 0.0  // top   
     ];
 
+    const COMPONENTS_PER_VERTEX: usize = 3;
+
     pub fn new() -> Self {
+        Triangle::with_usage(BufferUsage::default())
+    }
+
+    /// Creates the triangle with the given usage hint for its vertex buffer
+    pub fn with_usage(usage: BufferUsage) -> Self {
+        Triangle::create(&Triangle::VERTICES, usage)
+    }
+
+    /// Creates a triangle from the given (x, y, z) positions, in the order they're drawn
+    pub fn from_vertices(vertices: &[[f32; 3]; 3]) -> Self {
+        Triangle::create(vertices.as_flattened(), BufferUsage::default())
+    }
+
+    fn create(vertices: &[f32], usage: BufferUsage) -> Self {
         let vertex_array_object = VAOResource::new();
         gl_function(|| unsafe{ gl::BindVertexArray(vertex_array_object.handle().index()) });
 
         let vertex_buffer_object = VBOResource::new();
         gl_function(|| unsafe{ gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer_object.handle().index()) });
-        gl_function(|| unsafe{ gl::BufferData(gl::ARRAY_BUFFER, std::mem::size_of_val(&Triangle::VERTICES) as _, Triangle::VERTICES.as_ptr() as _, gl::STATIC_DRAW) });
+        gl_function(|| unsafe{ gl::BufferData(gl::ARRAY_BUFFER, std::mem::size_of_val(vertices) as _, vertices.as_ptr() as _, usage as _) });
 
-        gl_function(|| unsafe{ gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (std::mem::size_of::<f32>() * 3) as _, std::ptr::null()) });
+        check_array_buffer_bound(0);
+        gl_function(|| unsafe{ gl::VertexAttribPointer(0, Triangle::COMPONENTS_PER_VERTEX as _, gl::FLOAT, gl::FALSE, (std::mem::size_of::<f32>() * Triangle::COMPONENTS_PER_VERTEX) as _, std::ptr::null()) });
         gl_function(|| unsafe{ gl::EnableVertexAttribArray(0) });
 
-        Self { vertex_array_object, vertex_buffer_object }
+        let vertex_count = vertices.len() / Triangle::COMPONENTS_PER_VERTEX;
+        Self { vertex_array_object, vertex_buffer_object, vertex_count, transform: Mat4::IDENTITY }
     }
 
+    #[must_use] pub fn vertex_count(&self) -> usize { self.vertex_count }
+
+    /// Triangles are drawn without an index buffer, so this is always zero
+    #[must_use] pub fn index_count(&self) -> usize { 0 }
+
+    pub fn draw(&self) {
+        // Some drivers flag empty draws as errors, so skip the call entirely
+        if self.vertex_count == 0 {
+            return;
+        }
+
+        gl_function(|| unsafe{ gl::BindVertexArray(self.vertex_array_object.handle().index()) });
+        gl_function(|| unsafe{ gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count as _) });
+    }
+}
+
+impl Drawable for Triangle {
+    fn draw(&self) { Triangle::draw(self) }
+}
+
+impl Transformable for Triangle {
+    fn transform(&self) -> &Mat4 { &self.transform }
+    fn set_transform(&mut self, transform: Mat4) { self.transform = transform; }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// A single triangle covering the whole viewport, for post-processing passes which apply a
+/// shader to every pixel - e.g. drawing a 'Framebuffer''s texture to the screen
+///
+/// The positions are generated from 'gl_VertexID' by 'VERTEX_SHADER', so there's no vertex
+/// buffer. One oversized triangle avoids the seam (and duplicated fragments) along a quad's diagonal.
+pub struct FullscreenQuad {
+    vertex_array_object: VAOResource,
+}
+
+impl FullscreenQuad {
+    /// Outputs 'uv', texture coordinates from (0, 0) to (1, 1) across the viewport
+    pub const VERTEX_SHADER: &'static str = "#version 330 core
+out vec2 uv;
+
+void main()
+{
+    uv = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    gl_Position = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+    /// Core profiles can't draw without a vertex array bound, even if it has no attributes
+    pub fn new() -> Self {
+        Self { vertex_array_object: VAOResource::new() }
+    }
+
+    /// Links 'VERTEX_SHADER' with the given fragment shader, which should read 'in vec2 uv'
+    pub fn program(fragment_source: &str) -> Result<ShaderProgram> {
+        ShaderProgram::from_source(FullscreenQuad::VERTEX_SHADER, fragment_source)
+    }
+
+    /// Draws the triangle, bind a program (e.g. from 'program') first
     pub fn draw(&self) {
         gl_function(|| unsafe{ gl::BindVertexArray(self.vertex_array_object.handle().index()) });
         gl_function(|| unsafe{ gl::DrawArrays(gl::TRIANGLES, 0, 3) });
     }
+}
+
+impl Default for FullscreenQuad {
+    fn default() -> Self { Self::new() }
+}
+
+impl Drawable for FullscreenQuad {
+    fn draw(&self) { FullscreenQuad::draw(self) }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// An integer type which can be stored in an element buffer
+///
+/// Smaller types save memory and bandwidth, 'u16' is enough for meshes of up to 65536 vertices
+pub trait IndexType: Copy {
+    /// The matching 'GL_UNSIGNED_*' type passed to 'glDrawElements'
+    const GL_TYPE: GLenum;
+
+    fn to_usize(self) -> usize;
+}
+
+impl IndexType for u8 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_BYTE;
+    fn to_usize(self) -> usize { self as usize }
+}
+
+impl IndexType for u16 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_SHORT;
+    fn to_usize(self) -> usize { self as usize }
+}
+
+impl IndexType for u32 {
+    const GL_TYPE: GLenum = gl::UNSIGNED_INT;
+    fn to_usize(self) -> usize { self as usize }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// "Plain old data" which can be uploaded to a buffer as its raw bytes, e.g.
+/// '#[repr(C)] struct Vertex { position: [f32; 3], normal: [f32; 3], uv: [f32; 2] }' with
+/// 'bytemuck::Pod' and 'bytemuck::Zeroable' implemented (or derived with bytemuck's "derive" feature)
+pub use bytemuck::Pod;
+
+/// A floating point vertex attribute: 'components' floats (1 to 4) read from 'location'
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub components: u32,
+    /// Bytes from the start of the vertex
+    pub offset: usize,
+    /// 0 (the default) advances the attribute every vertex, 'n' advances it every 'n'
+    /// instances instead - 'glVertexAttribDivisor'
+    pub divisor: u32,
+}
+
+/// Describes how a vertex's fields map to shader attribute locations
+///
+/// Attributes are tightly packed in the order they're added, matching a '#[repr(C)]' struct
+/// of 'f32' arrays, e.g. position, normal and texture coordinates:
+/// 'VertexLayout::new().attribute(0, 3).attribute(1, 3).attribute(2, 2)'
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VertexLayout {
+    attributes: Vec<VertexAttribute>,
+    stride: usize,
+}
+
+impl VertexLayout {
+    pub fn new() -> Self {
+        Self { attributes: Vec::new(), stride: 0 }
+    }
+
+    /// A single (x, y, z) position at location 0
+    pub fn positions() -> Self {
+        Self::new().attribute(0, 3)
+    }
+
+    /// Appends an attribute of 'components' floats after the previous attribute
+    ///
+    /// 'location' is checked against 'GlLimits::max_vertex_attribs' when a mesh uses the layout,
+    /// as the layout may be built before there's a context to query
+    pub fn attribute(self, location: u32, components: u32) -> Self {
+        self.attribute_with_divisor(location, components, 0)
+    }
+
+    /// Appends a per-instance attribute, which advances once every 'divisor' instances when
+    /// drawing with e.g. 'Mesh::draw_instanced' - such as a per-instance offset or colour
+    ///
+    /// Instance 'n' reads the attribute from vertex 'n / divisor' of the buffer
+    pub fn per_instance_attribute(self, location: u32, components: u32, divisor: u32) -> Self {
+        assert!(divisor > 0, "Per-instance attributes need a divisor of at least 1");
+        self.attribute_with_divisor(location, components, divisor)
+    }
+
+    fn attribute_with_divisor(mut self, location: u32, components: u32, divisor: u32) -> Self {
+        assert!((1..=4).contains(&components), "Vertex attributes have between 1 and 4 components");
+
+        self.attributes.push(VertexAttribute { location, components, offset: 0, divisor });
+
+        let (offsets, stride) = compute_layout_offsets(&self.attributes);
+        for (attribute, offset) in self.attributes.iter_mut().zip(offsets) {
+            attribute.offset = offset;
+        }
+        self.stride = stride;
+        self
+    }
+
+    #[must_use] pub fn attributes(&self) -> &[VertexAttribute] { &self.attributes }
+
+    /// The size of a vertex in bytes
+    #[must_use] pub fn stride(&self) -> usize { self.stride }
+
+    /// Configures the attributes of the bound vertex array to read from 'buffer'
+    ///
+    /// Uses separate attribute formats and buffer bindings where supported, otherwise
+    /// 'glVertexAttribPointer' with 'buffer' bound to 'GL_ARRAY_BUFFER'
+    fn apply(&self, buffer: &VBOResource) {
+        if const { validation::should_validate() } {
+            let max_attributes = version::get_opengl_limits().max_vertex_attribs;
+            if let Some(attribute) = self.attributes.iter().find(|attribute| attribute.location as usize >= max_attributes) {
+                log::error!("Vertex attribute location {} exceeds the limit of {} attributes", attribute.location, max_attributes);
+                panic!("Vertex attribute location {} exceeds the limit of {} attributes", attribute.location, max_attributes);
+            }
+        }
+
+        if separate_attribute_format_supported() {
+            self.apply_formats(buffer);
+        } else {
+            gl_function(|| unsafe{ gl::BindBuffer(gl::ARRAY_BUFFER, buffer.handle().index()) });
+            self.apply_pointers();
+        }
+    }
+
+    /// The modern path - 'glVertexAttribFormat' describes each attribute once, and
+    /// 'glBindVertexBuffer' attaches the buffer, so swapping buffers only changes the binding
+    ///
+    /// The divisor belongs to the binding rather than the attribute, so each distinct divisor
+    /// gets its own binding of the same buffer
+    fn apply_formats(&self, buffer: &VBOResource) {
+        let mut divisors: Vec<u32> = Vec::new();
+
+        for attribute in &self.attributes {
+            let binding = match divisors.iter().position(|&divisor| divisor == attribute.divisor) {
+                Some(binding) => binding,
+                None => {
+                    divisors.push(attribute.divisor);
+                    divisors.len() - 1
+                },
+            } as u32;
+
+            gl_function(|| unsafe{
+                gl::VertexAttribFormat(attribute.location, attribute.components as _, gl::FLOAT, gl::FALSE, attribute.offset as _)
+            });
+            gl_function(|| unsafe{ gl::VertexAttribBinding(attribute.location, binding) });
+            gl_function(|| unsafe{ gl::EnableVertexAttribArray(attribute.location) });
+        }
+
+        for (binding, &divisor) in divisors.iter().enumerate() {
+            gl_function(|| unsafe{ gl::BindVertexBuffer(binding as _, buffer.handle().index(), 0, self.stride as _) });
+            gl_function(|| unsafe{ gl::VertexBindingDivisor(binding as _, divisor) });
+        }
+    }
+
+    /// Configures the attributes of the bound vertex array to read from the bound 'GL_ARRAY_BUFFER'
+    fn apply_pointers(&self) {
+        for attribute in &self.attributes {
+            check_array_buffer_bound(attribute.location);
+            gl_function(|| unsafe{
+                gl::VertexAttribPointer(attribute.location, attribute.components as _, gl::FLOAT, gl::FALSE, self.stride as _, attribute.offset as _)
+            });
+            gl_function(|| unsafe{ gl::EnableVertexAttribArray(attribute.location) });
+
+            if attribute.divisor != 0 {
+                gl_function(|| unsafe{ gl::VertexAttribDivisor(attribute.location, attribute.divisor) });
+            }
+        }
+    }
+}
+
+/// Separate attribute formats and bindings are core since OpenGL 4.3
+fn separate_attribute_format_supported() -> bool {
+    version::get_opengl_version().at_least(4, 3) || version::has_extension("GL_ARB_vertex_attrib_binding")
+}
+
+/// The byte offset of each attribute when tightly packed in order, and the resulting stride
+///
+/// Only the attributes' components are used, their current offsets are ignored
+pub fn compute_layout_offsets(attributes: &[VertexAttribute]) -> (Vec<usize>, usize) {
+    let mut stride = 0;
+    let offsets = attributes.iter()
+        .map(|attribute| {
+            let offset = stride;
+            stride += attribute.components as usize * std::mem::size_of::<f32>();
+            offset
+        })
+        .collect();
+
+    (offsets, stride)
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// How vertices are assembled into primitives
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PrimitiveMode {
+    Points = gl::POINTS,
+    Lines = gl::LINES,
+    LineStrip = gl::LINE_STRIP,
+    LineLoop = gl::LINE_LOOP,
+    #[default]
+    Triangles = gl::TRIANGLES,
+    /// Each vertex after the first two forms a triangle with the previous two - see
+    /// 'graphics::set_primitive_restart' for drawing several strips from one element buffer
+    TriangleStrip = gl::TRIANGLE_STRIP,
+    TriangleFan = gl::TRIANGLE_FAN,
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// Geometry made of (x, y, z) positions, or vertices described by a 'VertexLayout',
+/// optionally drawn through an index (element) buffer
+#[allow(dead_code)]
+pub struct Mesh {
+    vertex_array_object: VAOResource,
+    vertex_buffer_object: VBOResource,
+    element_buffer_object: Option<VBOResource>,
+    vertex_count: usize,
+    index_count: usize,
+    index_type: GLenum,
+    mode: PrimitiveMode,
+    transform: Mat4,
+}
+
+impl Mesh {
+    /// Creates a mesh drawn directly from its vertices, without an element buffer
+    pub fn new(vertices: &[[f32; 3]]) -> Self {
+        Mesh::create::<_, u32>(vertices, &VertexLayout::positions(), None, BufferUsage::default())
+    }
+
+    /// Creates a mesh drawn through an element buffer of 'u8', 'u16' or 'u32' indices
+    pub fn indexed<I: IndexType>(vertices: &[[f32; 3]], indices: &[I]) -> Self {
+        Mesh::create(vertices, &VertexLayout::positions(), Some(indices), BufferUsage::default())
+    }
+
+    /// As 'new', with the given usage hint for the vertex buffer
+    pub fn new_with_usage(vertices: &[[f32; 3]], usage: BufferUsage) -> Self {
+        Mesh::create::<_, u32>(vertices, &VertexLayout::positions(), None, usage)
+    }
+
+    /// As 'indexed', with the given usage hint for the vertex and element buffers
+    pub fn indexed_with_usage<I: IndexType>(vertices: &[[f32; 3]], indices: &[I], usage: BufferUsage) -> Self {
+        Mesh::create(vertices, &VertexLayout::positions(), Some(indices), usage)
+    }
+
+    /// Creates a mesh from vertices of any 'Pod' type, with attributes configured by 'layout'
+    ///
+    /// Fails with 'InvalidInput' if the layout's stride doesn't match the size of 'V', which
+    /// would otherwise misread every vertex after the first
+    pub fn from_vertices<V: Pod>(vertices: &[V], layout: &VertexLayout) -> Result<Self> {
+        Mesh::check_layout::<V>(layout)?;
+        Ok(Mesh::create::<_, u32>(vertices, layout, None, BufferUsage::default()))
+    }
+
+    /// As 'from_vertices', drawn through an element buffer
+    pub fn indexed_from_vertices<V: Pod, I: IndexType>(vertices: &[V], layout: &VertexLayout, indices: &[I]) -> Result<Self> {
+        Mesh::check_layout::<V>(layout)?;
+        Ok(Mesh::create(vertices, layout, Some(indices), BufferUsage::default()))
+    }
+
+    fn check_layout<V: Pod>(layout: &VertexLayout) -> Result<()> {
+        if layout.stride() == std::mem::size_of::<V>() {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Vertex layout stride ({} bytes) doesn't match the vertex size ({} bytes).", layout.stride(), std::mem::size_of::<V>())
+            ))
+        }
+    }
+
+    fn create<V: Pod, I: IndexType>(vertices: &[V], layout: &VertexLayout, indices: Option<&[I]>, usage: BufferUsage) -> Self {
+        // An out of range index reads past the end of the vertex buffer, which is undefined behaviour
+        if const { validation::should_validate() } {
+            if let Some(index) = indices.and_then(|indices| indices.iter().find(|index| index.to_usize() >= vertices.len())) {
+                let message = format!("Mesh index {} is out of range for {} vertices", index.to_usize(), vertices.len());
+                log::error!("{}", message);
+                panic!("{}", message);
+            }
+        }
+
+        let vertex_array_object = VAOResource::new();
+        gl_function(|| unsafe{ gl::BindVertexArray(vertex_array_object.handle().index()) });
+
+        let vertex_buffer_object = VBOResource::new();
+        gl_function(|| unsafe{ gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer_object.handle().index()) });
+        gl_function(|| unsafe{ gl::BufferData(gl::ARRAY_BUFFER, std::mem::size_of_val(vertices) as _, vertices.as_ptr() as _, usage as _) });
+
+        layout.apply(&vertex_buffer_object);
+
+        // The element buffer binding is part of the vertex array's state
+        let element_buffer_object = indices.map(|indices| {
+            let element_buffer_object = VBOResource::new();
+            gl_function(|| unsafe{ gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, element_buffer_object.handle().index()) });
+            gl_function(|| unsafe{ gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, std::mem::size_of_val(indices) as _, indices.as_ptr() as _, usage as _) });
+            element_buffer_object
+        });
+
+        Self {
+            vertex_array_object,
+            vertex_buffer_object,
+            element_buffer_object,
+            vertex_count: vertices.len(),
+            index_count: indices.map_or(0, <[I]>::len),
+            index_type: I::GL_TYPE,
+            mode: PrimitiveMode::default(),
+            transform: Mat4::IDENTITY,
+        }
+    }
+
+    #[must_use] pub fn vertex_count(&self) -> usize { self.vertex_count }
+    #[must_use] pub fn index_count(&self) -> usize { self.index_count }
+    #[must_use] pub fn has_indices(&self) -> bool { self.element_buffer_object.is_some() }
+    #[must_use] pub fn mode(&self) -> PrimitiveMode { self.mode }
+
+    /// Sets how the vertices are assembled into primitives, triangles by default
+    pub fn set_mode(&mut self, mode: PrimitiveMode) { self.mode = mode; }
+
+    /// Draws the mesh's primitives, through the element buffer if it has one
+    pub fn draw(&self) {
+        gl_function(|| unsafe{ gl::BindVertexArray(self.vertex_array_object.handle().index()) });
+
+        if self.has_indices() {
+            if self.index_count > 0 {
+                gl_function(|| unsafe{ gl::DrawElements(self.mode as GLenum, self.index_count as _, self.index_type, std::ptr::null()) });
+            }
+        } else if self.vertex_count > 0 {
+            gl_function(|| unsafe{ gl::DrawArrays(self.mode as GLenum, 0, self.vertex_count as _) });
+        }
+    }
+
+    /// Draws 'instances' copies of the indexed mesh - 'glDrawElementsInstanced'
+    ///
+    /// Shaders tell the copies apart with 'gl_InstanceID'
+    pub fn draw_instanced(&self, instances: u32) -> Result<()> {
+        self.require_indices("draw_instanced")?;
+        if self.index_count == 0 || instances == 0 {
+            return Ok(());
+        }
+
+        gl_function(|| unsafe{ gl::BindVertexArray(self.vertex_array_object.handle().index()) });
+        gl_function(|| unsafe{
+            gl::DrawElementsInstanced(self.mode as GLenum, self.index_count as _, self.index_type, std::ptr::null(), instances as _)
+        });
+        Ok(())
+    }
+
+    /// Draws the indexed mesh with 'base' added to every index - 'glDrawElementsBaseVertex'
+    ///
+    /// Lets several meshes share one vertex and element buffer pair, each with indices
+    /// relative to its own first vertex
+    pub fn draw_base_vertex(&self, base: i32) -> Result<()> {
+        self.require_indices("draw_base_vertex")?;
+        if self.index_count == 0 {
+            return Ok(());
+        }
+
+        gl_function(|| unsafe{ gl::BindVertexArray(self.vertex_array_object.handle().index()) });
+        gl_function(|| unsafe{
+            gl::DrawElementsBaseVertex(self.mode as GLenum, self.index_count as _, self.index_type, std::ptr::null_mut(), base)
+        });
+        Ok(())
+    }
+
+    fn require_indices(&self, draw: &str) -> Result<()> {
+        if self.has_indices() {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidInput, format!("'{}' requires a mesh with an element buffer.", draw)))
+        }
+    }
+}
+
+impl Drawable for Mesh {
+    fn draw(&self) { Mesh::draw(self) }
+}
+
+impl Transformable for Mesh {
+    fn transform(&self) -> &Mat4 { &self.transform }
+    fn set_transform(&mut self, transform: Mat4) { self.transform = transform; }
 }
\ No newline at end of file
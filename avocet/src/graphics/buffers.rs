@@ -1,19 +1,40 @@
 use crate::{
-    graphics::ResourceHandle,
+    graphics::{RawHandle, ResourceHandle},
     validation::gl_function,
+    version,
 };
 
+use std::{
+    cell::Cell,
+    io::{Error, ErrorKind, Result},
+};
+
+use gl::types::*;
+
 pub trait VertexResourceLifecycle {
+    /// The namespace of the object for 'glObjectLabel', e.g. 'GL_BUFFER'
+    const IDENTIFIER: GLenum;
+
     fn generate<const N: usize>() -> [ResourceHandle; N];
     fn destroy(indices: &[ResourceHandle]);
+
+    /// 'glGen*' only reserves a name, the object itself is created when first bound.
+    /// Binds the object without disturbing the current state, where the target is known
+    fn create_object(_handle: &ResourceHandle) {}
+}
+
+/// Owns 'N' objects, deleting them when dropped - unless wrapped with 'from_raw_unowned'
+pub struct VertexResource<const N: usize, T: VertexResourceLifecycle>([ResourceHandle; N], std::marker::PhantomData<T>, bool);
+
+impl<const N: usize, T: VertexResourceLifecycle> PartialEq for VertexResource<N, T> {
+    fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
 }
 
-#[derive(PartialEq, Eq)]
-pub struct VertexResource<const N: usize, T: VertexResourceLifecycle>([ResourceHandle; N], std::marker::PhantomData<T>);
+impl<const N: usize, T: VertexResourceLifecycle> Eq for VertexResource<N, T> {}
 
 impl<const N: usize, T: VertexResourceLifecycle> VertexResource<N, T> {
     pub fn new() -> Self {
-        Self (T::generate(), std::marker::PhantomData)
+        Self (T::generate(), std::marker::PhantomData, true)
     }
 
     pub fn handle_at(&self, idx: usize) -> &ResourceHandle { &self.0[idx] }
@@ -21,11 +42,48 @@ impl<const N: usize, T: VertexResourceLifecycle> VertexResource<N, T> {
 
 impl<T: VertexResourceLifecycle> VertexResource<1, T> {
     pub fn handle(&self) -> &ResourceHandle { &self.0[0] }
+
+    /// Creates 'M' independent resources from a single 'glGen*' call
+    ///
+    /// Each resource still owns its handle and deletes it individually when dropped
+    pub fn new_batch<const M: usize>() -> [Self; M] {
+        T::generate::<M>().map(|handle| Self([handle], std::marker::PhantomData, true))
+    }
+
+    /// Wraps an object created and deleted elsewhere, e.g. by another GL library - dropping
+    /// the resource leaves the object alone
+    ///
+    /// # Safety
+    /// 'handle' must name an object of this resource's type, and its owner mustn't delete it
+    /// while the resource is in use. Avocet never deletes it, so it can't be freed twice.
+    pub unsafe fn from_raw_unowned(handle: GLuint) -> Self {
+        Self([ResourceHandle(handle)], std::marker::PhantomData, false)
+    }
+
+    /// False for resources wrapped with 'from_raw_unowned'
+    #[must_use] pub fn is_owned(&self) -> bool { self.2 }
+
+    /// Creates the resource with a label shown in graphics debuggers - see 'set_label'
+    pub fn new_labeled(label: &str) -> Self {
+        let resource = Self::new();
+        T::create_object(resource.handle());
+        resource.set_label(label);
+        resource
+    }
+
+    /// Names the object in graphics debuggers such as RenderDoc - 'glObjectLabel'
+    ///
+    /// Requires OpenGL 4.3 or 'GL_KHR_debug'. The object must have been bound at least once.
+    pub fn set_label(&self, label: &str) {
+        self.handle().set_label(T::IDENTIFIER, label);
+    }
 }
 
 impl<const N: usize, T: VertexResourceLifecycle> Drop for VertexResource<N, T> {
     fn drop(&mut self) {
-        T::destroy(&self.0);
+        if self.2 {
+            T::destroy(&self.0);
+        }
     }
 }
 
@@ -34,6 +92,8 @@ impl<const N: usize, T: VertexResourceLifecycle> Drop for VertexResource<N, T> {
 pub struct VertexArrayLifecycle;
 
 impl VertexResourceLifecycle for VertexArrayLifecycle {
+    const IDENTIFIER: GLenum = gl::VERTEX_ARRAY;
+
     fn generate<const N: usize>() -> [ResourceHandle; N] {
         let mut result = [const { ResourceHandle(0) }; N];
         unsafe{ gl_function(|| gl::GenVertexArrays(N as _, result.as_mut_ptr() as _)) };
@@ -43,11 +103,22 @@ impl VertexResourceLifecycle for VertexArrayLifecycle {
     fn destroy(handles: &[ResourceHandle]) {
         unsafe{ gl_function(|| gl::DeleteVertexArrays(handles.len() as _, handles.as_ptr() as _)) };
     }
+
+    fn create_object(handle: &ResourceHandle) {
+        let mut previous = 0;
+        unsafe{
+            gl_function(|| gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut previous));
+            gl_function(|| gl::BindVertexArray(handle.index()));
+            gl_function(|| gl::BindVertexArray(previous as _));
+        }
+    }
 }
 
 pub struct VertexBufferLifecycle;
 
 impl VertexResourceLifecycle for VertexBufferLifecycle {
+    const IDENTIFIER: GLenum = gl::BUFFER;
+
     fn generate<const N: usize>() -> [ResourceHandle; N] {
         let mut result = [const { ResourceHandle(0) }; N];
         unsafe { gl_function(|| gl::GenBuffers(N as _, result.as_mut_ptr() as _)) };
@@ -57,9 +128,258 @@ impl VertexResourceLifecycle for VertexBufferLifecycle {
     fn destroy(handles: &[ResourceHandle]) {
         unsafe { gl_function(|| gl::DeleteBuffers(handles.len() as _, handles.as_ptr() as _)) };
     }
+
+    fn create_object(handle: &ResourceHandle) {
+        // 'GL_COPY_WRITE_BUFFER' has no effect on rendering, so is safe to borrow
+        let mut previous = 0;
+        unsafe{
+            gl_function(|| gl::GetIntegerv(gl::COPY_WRITE_BUFFER_BINDING, &mut previous));
+            gl_function(|| gl::BindBuffer(gl::COPY_WRITE_BUFFER, handle.index()));
+            gl_function(|| gl::BindBuffer(gl::COPY_WRITE_BUFFER, previous as _));
+        }
+    }
 }
 
 // ------------------------------------------------------------------------------------------
 
 pub type VAOResource = VertexResource<1, VertexArrayLifecycle>;
-pub type VBOResource = VertexResource<1, VertexBufferLifecycle>;
\ No newline at end of file
+pub type VBOResource = VertexResource<1, VertexBufferLifecycle>;
+
+// ------------------------------------------------------------------------------------------
+
+/// A hint to the driver of how a buffer's contents are accessed, which affects where the
+/// buffer is placed in memory - 'glBufferData'
+///
+/// - 'Static': written once, used many times
+/// - 'Dynamic': written repeatedly, used many times
+/// - 'Stream': written once, used at most a few times
+///
+/// '*Draw' contents are written by the application and read by GL, '*Read' contents are
+/// written by GL and read by the application, '*Copy' contents are written and read by GL
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum BufferUsage {
+    #[default]
+    StaticDraw = gl::STATIC_DRAW,
+    StaticRead = gl::STATIC_READ,
+    StaticCopy = gl::STATIC_COPY,
+    DynamicDraw = gl::DYNAMIC_DRAW,
+    DynamicRead = gl::DYNAMIC_READ,
+    DynamicCopy = gl::DYNAMIC_COPY,
+    StreamDraw = gl::STREAM_DRAW,
+    StreamRead = gl::STREAM_READ,
+    StreamCopy = gl::STREAM_COPY,
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// A buffer for use as a shader storage block (SSBO) - requires OpenGL 4.3
+///
+/// The contents must match the 'std430' layout of the block declared in the shader:
+/// - scalars are 4 byte aligned, 'vec2' 8 byte aligned and 'vec3'/'vec4' 16 byte aligned
+/// - a 'vec3' therefore occupies 16 bytes within an array, so pad to '[f32; 4]' on the Rust side
+/// - unlike 'std140', arrays of scalars and 'vec2' are tightly packed
+///
+/// Typical usage with a 'ComputeProgram': upload, 'bind_to_point' the binding index declared
+/// by 'layout(std430, binding = n)', dispatch with 'MemoryBarrier::BUFFER_UPDATE' and 'read_back'.
+pub struct ShaderStorageBuffer {
+    resource: VBOResource,
+    size: Cell<usize>,
+}
+
+impl ShaderStorageBuffer {
+    pub fn new() -> Result<Self> {
+        let version = version::get_opengl_version();
+        if !version.at_least(4, 3) {
+            return Err(Error::new(ErrorKind::Unsupported, "Shader storage buffers require OpenGL 4.3."));
+        }
+
+        Ok(Self { resource: VBOResource::new(), size: Cell::new(0) })
+    }
+
+    /// Wraps a buffer created by other code, holding 'size' bytes, without taking ownership
+    ///
+    /// # Safety
+    /// See 'VertexResource::from_raw_unowned' - the owner must keep the buffer alive while it's used
+    pub unsafe fn from_raw_unowned(handle: GLuint, size: usize) -> Self {
+        Self { resource: VBOResource::from_raw_unowned(handle), size: Cell::new(size) }
+    }
+
+    pub fn bind(&self) {
+        unsafe{ gl_function(|| gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.resource.handle().index())) };
+    }
+
+    /// Binds this buffer to the indexed binding point used by 'layout(binding = index)' - 'glBindBufferBase'
+    pub fn bind_to_point(&self, index: u32) {
+        unsafe{ gl_function(|| gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, index, self.resource.handle().index())) };
+    }
+
+    /// Replaces the buffer's contents, resizing it to fit the data
+    pub fn upload(&self, data: &[u8]) {
+        self.bind();
+        unsafe{
+            gl_function(|| gl::BufferData(gl::SHADER_STORAGE_BUFFER, data.len() as _, data.as_ptr() as _, BufferUsage::DynamicCopy as _));
+        }
+        self.size.set(data.len());
+    }
+
+    /// Copies the buffer's contents back to the CPU - 'glGetBufferSubData'
+    ///
+    /// Shader writes must be made visible first with 'MemoryBarrier::BUFFER_UPDATE'
+    pub fn read_back(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0; self.size.get()];
+        self.bind();
+        unsafe{
+            gl_function(|| gl::GetBufferSubData(gl::SHADER_STORAGE_BUFFER, 0, data.len() as _, data.as_mut_ptr() as _));
+        }
+        data
+    }
+
+    /// The size of the buffer's contents in bytes
+    #[must_use] pub fn size(&self) -> usize { self.size.get() }
+
+    #[must_use] pub fn resource(&self) -> &VBOResource { &self.resource }
+
+    /// The buffer's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// A vertex buffer whose contents are updated after creation, e.g. streamed geometry
+pub struct DynamicBuffer {
+    resource: VBOResource,
+    size: usize,
+    usage: BufferUsage,
+}
+
+impl DynamicBuffer {
+    /// Allocates 'size' bytes of uninitialised storage
+    pub fn new(size: usize, usage: BufferUsage) -> Self {
+        let buffer = Self { resource: VBOResource::new(), size, usage };
+        buffer.bind();
+        unsafe{ gl_function(|| gl::BufferData(gl::ARRAY_BUFFER, size as _, std::ptr::null(), usage as _)) };
+        buffer
+    }
+
+    /// Wraps a buffer created by other code, with 'size' bytes of storage, without taking ownership
+    ///
+    /// # Safety
+    /// See 'VertexResource::from_raw_unowned' - the owner must keep the buffer alive while it's used
+    pub unsafe fn from_raw_unowned(handle: GLuint, size: usize, usage: BufferUsage) -> Self {
+        Self { resource: VBOResource::from_raw_unowned(handle), size, usage }
+    }
+
+    pub fn bind(&self) {
+        unsafe{ gl_function(|| gl::BindBuffer(gl::ARRAY_BUFFER, self.resource.handle().index())) };
+    }
+
+    /// Copies 'data' into the buffer at 'offset' bytes - 'glBufferSubData'
+    pub fn upload(&self, offset: usize, data: &[u8]) -> Result<()> {
+        self.check_range(offset, data.len())?;
+
+        self.bind();
+        unsafe{ gl_function(|| gl::BufferSubData(gl::ARRAY_BUFFER, offset as _, data.len() as _, data.as_ptr() as _)) };
+        Ok(())
+    }
+
+    /// Maps the whole buffer for writing, see 'map_write_range'
+    pub fn map_write(&mut self) -> Result<MappedBuffer<'_>> {
+        self.map_write_range(0, self.size)
+    }
+
+    /// Maps 'length' bytes from 'offset' for writing - 'glMapBufferRange' with 'GL_MAP_WRITE_BIT'
+    ///
+    /// Writes go directly to the driver's memory rather than being copied by 'glBufferSubData'.
+    /// The buffer is unmapped when the returned guard is dropped. The guard borrows the buffer
+    /// mutably, so it can't be bound, uploaded to or drawn from while mapped.
+    pub fn map_write_range(&mut self, offset: usize, length: usize) -> Result<MappedBuffer<'_>> {
+        self.check_range(offset, length)?;
+        if length == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "Unable to map an empty range."));
+        }
+
+        self.bind();
+        let mut pointer = std::ptr::null_mut();
+        unsafe{
+            gl_function(|| pointer = gl::MapBufferRange(gl::ARRAY_BUFFER, offset as _, length as _, gl::MAP_WRITE_BIT));
+        }
+
+        if pointer.is_null() {
+            return Err(Error::other("Failed to map buffer."));
+        }
+
+        // The mapping stays valid until 'glUnmapBuffer', which the guard's lifetime enforces
+        let data = unsafe{ std::slice::from_raw_parts_mut(pointer as *mut u8, length) };
+        Ok(MappedBuffer { buffer: self, data, unmapped: false })
+    }
+
+    fn check_range(&self, offset: usize, length: usize) -> Result<()> {
+        match offset.checked_add(length) {
+            Some(end) if end <= self.size => Ok(()),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Range {}..{} is out of bounds for a buffer of {} bytes.", offset, offset.saturating_add(length), self.size)
+            )),
+        }
+    }
+
+    /// The size of the buffer in bytes
+    #[must_use] pub fn size(&self) -> usize { self.size }
+    #[must_use] pub fn usage(&self) -> BufferUsage { self.usage }
+
+    #[must_use] pub fn resource(&self) -> &VBOResource { &self.resource }
+
+    /// The buffer's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+}
+
+/// A writable view of a mapped 'DynamicBuffer', unmapped when dropped
+pub struct MappedBuffer<'a> {
+    buffer: &'a mut DynamicBuffer,
+    data: &'a mut [u8],
+    unmapped: bool,
+}
+
+impl MappedBuffer<'_> {
+    /// Unmaps the buffer - 'glUnmapBuffer'
+    ///
+    /// Fails if the data store was corrupted whilst mapped (e.g. by a display mode change),
+    /// in which case the contents are undefined and must be written again
+    pub fn unmap(mut self) -> Result<()> {
+        self.unmapped = true;
+        unmap(self.buffer)
+    }
+}
+
+fn unmap(buffer: &DynamicBuffer) -> Result<()> {
+    buffer.bind();
+    let mut intact = gl::TRUE;
+    unsafe{ gl_function(|| intact = gl::UnmapBuffer(gl::ARRAY_BUFFER)) };
+
+    if intact == gl::TRUE {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::InvalidData, "Buffer data store was corrupted whilst mapped."))
+    }
+}
+
+impl std::ops::Deref for MappedBuffer<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] { self.data }
+}
+
+impl std::ops::DerefMut for MappedBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] { self.data }
+}
+
+impl Drop for MappedBuffer<'_> {
+    fn drop(&mut self) {
+        if !self.unmapped {
+            if let Err(error) = unmap(self.buffer) {
+                log::error!("{}", error);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,35 @@
+/// A linear RGBA colour with components between 0.0 and 1.0
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::rgb(1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Self = Self::new(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self { Self { r, g, b, a } }
+
+    /// An opaque colour
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self { Self::new(r, g, b, 1.0) }
+
+    /// Converts 8-bit channels (e.g. from 'read_pixels') to the 0.0 to 1.0 range
+    pub fn from_rgba8(rgba: [u8; 4]) -> Self {
+        let [r, g, b, a] = rgba.map(|channel| channel as f32 / u8::MAX as f32);
+        Self::new(r, g, b, a)
+    }
+
+    #[must_use] pub const fn to_array(self) -> [f32; 4] { [self.r, self.g, self.b, self.a] }
+}
+
+impl From<[f32; 4]> for Color {
+    fn from(value: [f32; 4]) -> Self { Self::new(value[0], value[1], value[2], value[3]) }
+}
+
+impl From<[f32; 3]> for Color {
+    fn from(value: [f32; 3]) -> Self { Self::rgb(value[0], value[1], value[2]) }
+}
\ No newline at end of file
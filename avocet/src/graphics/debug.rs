@@ -0,0 +1,111 @@
+use crate::{
+    graphics::{check_array_buffer_bound, BufferUsage, ShaderProgram, VAOResource, VBOResource},
+    validation::gl_function,
+};
+
+use std::io::Result;
+
+use gl::types::*;
+
+const VERTEX_SHADER: &str = "#version 330 core
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec3 color;
+
+out vec3 vertex_color;
+
+void main()
+{
+    vertex_color = color;
+    gl_Position = vec4(position, 1.0);
+}";
+
+const FRAGMENT_SHADER: &str = "#version 330 core
+in vec3 vertex_color;
+
+out vec4 frag_color;
+
+void main()
+{
+    frag_color = vec4(vertex_color, 1.0);
+}";
+
+/// Accumulates coloured line segments and draws them all with a single 'glDrawArrays(GL_LINES)'
+///
+/// Intended for visualising things like normals and bounding boxes without building a mesh.
+/// Positions are passed straight through to clip space.
+pub struct DebugDraw {
+    program: ShaderProgram,
+    vertex_array_object: VAOResource,
+    vertex_buffer_object: VBOResource,
+    vertices: Vec<f32>,
+    capacity: usize,
+}
+
+impl DebugDraw {
+    /// Position followed by color
+    const FLOATS_PER_VERTEX: usize = 6;
+
+    pub fn new() -> Result<Self> {
+        let program = ShaderProgram::from_source(VERTEX_SHADER, FRAGMENT_SHADER)?;
+
+        let vertex_array_object = VAOResource::new();
+        let vertex_buffer_object = VBOResource::new();
+
+        unsafe{
+            gl_function(|| gl::BindVertexArray(vertex_array_object.handle().index()));
+            gl_function(|| gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer_object.handle().index()));
+
+            let stride = (std::mem::size_of::<f32>() * DebugDraw::FLOATS_PER_VERTEX) as GLsizei;
+            let color_offset = std::mem::size_of::<f32>() * 3;
+            check_array_buffer_bound(0);
+            gl_function(|| gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, std::ptr::null()));
+            gl_function(|| gl::EnableVertexAttribArray(0));
+            gl_function(|| gl::VertexAttribPointer(1, 3, gl::FLOAT, gl::FALSE, stride, color_offset as _));
+            gl_function(|| gl::EnableVertexAttribArray(1));
+        }
+
+        Ok(Self { program, vertex_array_object, vertex_buffer_object, vertices: Vec::new(), capacity: 0 })
+    }
+
+    /// Queues a line segment to be drawn by the next 'flush'
+    pub fn line(&mut self, from: [f32; 3], to: [f32; 3], color: [f32; 3]) {
+        self.vertices.extend_from_slice(&from);
+        self.vertices.extend_from_slice(&color);
+        self.vertices.extend_from_slice(&to);
+        self.vertices.extend_from_slice(&color);
+    }
+
+    /// The number of lines queued since the last 'flush'
+    #[must_use] pub fn line_count(&self) -> usize { self.vertices.len() / (DebugDraw::FLOATS_PER_VERTEX * 2) }
+
+    /// Uploads and draws all queued lines, then clears the queue
+    ///
+    /// Leaves the debug program and vertex array bound
+    pub fn flush(&mut self) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        let size = std::mem::size_of_val(self.vertices.as_slice());
+        let vertex_count = self.vertices.len() / DebugDraw::FLOATS_PER_VERTEX;
+
+        unsafe{
+            gl_function(|| gl::BindBuffer(gl::ARRAY_BUFFER, self.vertex_buffer_object.handle().index()));
+
+            // Only reallocate when the lines no longer fit, otherwise overwrite the existing storage
+            if size > self.capacity {
+                gl_function(|| gl::BufferData(gl::ARRAY_BUFFER, size as _, std::ptr::null(), BufferUsage::StreamDraw as _));
+                self.capacity = size;
+            }
+            gl_function(|| gl::BufferSubData(gl::ARRAY_BUFFER, 0, size as _, self.vertices.as_ptr() as _));
+        }
+
+        self.program.bind();
+        unsafe{
+            gl_function(|| gl::BindVertexArray(self.vertex_array_object.handle().index()));
+            gl_function(|| gl::DrawArrays(gl::LINES, 0, vertex_count as _));
+        }
+
+        self.vertices.clear();
+    }
+}
\ No newline at end of file
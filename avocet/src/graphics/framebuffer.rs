@@ -0,0 +1,341 @@
+use crate::{
+    graphics::{check_sample_count, Color, ResourceHandle, Texture2D, Texture2DMultisample, VertexResource, VertexResourceLifecycle},
+    validation::gl_function,
+    version,
+};
+
+use std::{
+    cell::Cell,
+    io::{Error, ErrorKind, Result},
+};
+
+use gl::types::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u32)]
+pub enum RenderbufferFormat {
+    Depth16 = gl::DEPTH_COMPONENT16,
+    Depth24 = gl::DEPTH_COMPONENT24,
+    Depth32F = gl::DEPTH_COMPONENT32F,
+    Depth24Stencil8 = gl::DEPTH24_STENCIL8,
+    Depth32FStencil8 = gl::DEPTH32F_STENCIL8,
+    Stencil8 = gl::STENCIL_INDEX8,
+}
+
+impl RenderbufferFormat {
+    /// The framebuffer attachment point matching this format
+    const fn attachment(self) -> GLenum {
+        match self {
+            RenderbufferFormat::Depth24Stencil8 | RenderbufferFormat::Depth32FStencil8 => gl::DEPTH_STENCIL_ATTACHMENT,
+            RenderbufferFormat::Stencil8 => gl::STENCIL_ATTACHMENT,
+            _ => gl::DEPTH_ATTACHMENT,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+
+pub struct FramebufferLifecycle;
+
+impl VertexResourceLifecycle for FramebufferLifecycle {
+    const IDENTIFIER: GLenum = gl::FRAMEBUFFER;
+
+    fn generate<const N: usize>() -> [ResourceHandle; N] {
+        let mut result = [const { ResourceHandle(0) }; N];
+        unsafe{ gl_function(|| gl::GenFramebuffers(N as _, result.as_mut_ptr() as _)) };
+        result
+    }
+
+    fn destroy(handles: &[ResourceHandle]) {
+        unsafe{ gl_function(|| gl::DeleteFramebuffers(handles.len() as _, handles.as_ptr() as _)) };
+    }
+
+    fn create_object(handle: &ResourceHandle) {
+        let mut previous = 0;
+        unsafe{
+            gl_function(|| gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous));
+            gl_function(|| gl::BindFramebuffer(gl::READ_FRAMEBUFFER, handle.index()));
+            gl_function(|| gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous as _));
+        }
+    }
+}
+
+pub struct RenderbufferLifecycle;
+
+impl VertexResourceLifecycle for RenderbufferLifecycle {
+    const IDENTIFIER: GLenum = gl::RENDERBUFFER;
+
+    fn generate<const N: usize>() -> [ResourceHandle; N] {
+        let mut result = [const { ResourceHandle(0) }; N];
+        unsafe{ gl_function(|| gl::GenRenderbuffers(N as _, result.as_mut_ptr() as _)) };
+        result
+    }
+
+    fn destroy(handles: &[ResourceHandle]) {
+        unsafe{ gl_function(|| gl::DeleteRenderbuffers(handles.len() as _, handles.as_ptr() as _)) };
+    }
+
+    fn create_object(handle: &ResourceHandle) {
+        let mut previous = 0;
+        unsafe{
+            gl_function(|| gl::GetIntegerv(gl::RENDERBUFFER_BINDING, &mut previous));
+            gl_function(|| gl::BindRenderbuffer(gl::RENDERBUFFER, handle.index()));
+            gl_function(|| gl::BindRenderbuffer(gl::RENDERBUFFER, previous as _));
+        }
+    }
+}
+
+pub type FBOResource = VertexResource<1, FramebufferLifecycle>;
+pub type RBOResource = VertexResource<1, RenderbufferLifecycle>;
+
+// ------------------------------------------------------------------------------------------
+
+pub struct Renderbuffer {
+    resource: RBOResource,
+    format: Cell<Option<RenderbufferFormat>>,
+}
+
+impl Renderbuffer {
+    pub fn new() -> Self {
+        Self { resource: RBOResource::new(), format: Cell::new(None) }
+    }
+
+    /// Allocates the storage for this renderbuffer - 'glRenderbufferStorage'
+    pub fn storage(&self, format: RenderbufferFormat, width: u32, height: u32) {
+        self.bind();
+        unsafe{ gl_function(|| gl::RenderbufferStorage(gl::RENDERBUFFER, format as GLenum, width as _, height as _)) };
+        self.format.set(Some(format));
+    }
+
+    /// Allocates storage with 'samples' samples per pixel - 'glRenderbufferStorageMultisample'
+    ///
+    /// Every attachment of a framebuffer must have the same sample count, so this is the depth
+    /// buffer to pair with a 'Texture2DMultisample'
+    pub fn storage_multisample(&self, format: RenderbufferFormat, samples: u32, width: u32, height: u32) -> Result<()> {
+        check_sample_count(samples)?;
+
+        self.bind();
+        unsafe{
+            gl_function(|| gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, samples as _, format as GLenum, width as _, height as _));
+        }
+        self.format.set(Some(format));
+        Ok(())
+    }
+
+    pub fn bind(&self) {
+        unsafe{ gl_function(|| gl::BindRenderbuffer(gl::RENDERBUFFER, self.resource.handle().index())) };
+    }
+
+    /// The format of the allocated storage, or 'None' if 'storage' hasn't been called yet
+    #[must_use] pub fn format(&self) -> Option<RenderbufferFormat> { self.format.get() }
+
+    #[must_use] pub fn resource(&self) -> &RBOResource { &self.resource }
+}
+
+impl Default for Renderbuffer {
+    fn default() -> Self { Self::new() }
+}
+
+// ------------------------------------------------------------------------------------------
+
+pub struct Framebuffer {
+    resource: FBOResource,
+    /// Bit 'n' is set once colour attachment 'n' has a texture
+    color_attachments: Cell<u32>,
+}
+
+impl Framebuffer {
+    pub fn new() -> Self {
+        Self { resource: FBOResource::new(), color_attachments: Cell::new(0) }
+    }
+
+    pub fn bind(&self) {
+        unsafe{ gl_function(|| gl::BindFramebuffer(gl::FRAMEBUFFER, self.resource.handle().index())) };
+    }
+
+    /// Binds the default framebuffer (the window's back buffer)
+    pub fn bind_default() {
+        unsafe{ gl_function(|| gl::BindFramebuffer(gl::FRAMEBUFFER, 0)) };
+    }
+
+    /// Attaches a depth (and/or stencil) renderbuffer - the attachment point is
+    /// chosen from the renderbuffer's format, so 'DEPTH24_STENCIL8' attaches to both
+    ///
+    /// Leaves this framebuffer bound
+    pub fn attach_depth(&self, renderbuffer: &Renderbuffer) {
+        let format = renderbuffer.format()
+            .expect("Renderbuffer storage must be allocated before it is attached");
+
+        self.bind();
+        unsafe{
+            gl_function(|| gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                format.attachment(),
+                gl::RENDERBUFFER,
+                renderbuffer.resource().handle().index()
+            ));
+        }
+    }
+
+    /// Attaches a texture to colour attachment 'index' - 'glFramebufferTexture2D'
+    ///
+    /// Leaves this framebuffer bound
+    pub fn attach_color(&self, index: u32, texture: &Texture2D) -> Result<()> {
+        self.attach_color_texture(index, gl::TEXTURE_2D, texture.resource().handle())
+    }
+
+    /// Attaches a multisampled texture to colour attachment 'index', see 'blit_to' to resolve it
+    ///
+    /// Leaves this framebuffer bound
+    pub fn attach_color_multisample(&self, index: u32, texture: &Texture2DMultisample) -> Result<()> {
+        self.attach_color_texture(index, gl::TEXTURE_2D_MULTISAMPLE, texture.resource().handle())
+    }
+
+    fn attach_color_texture(&self, index: u32, target: GLenum, texture: &ResourceHandle) -> Result<()> {
+        // The attachment mask has one bit per attachment, and no driver supports more than 32
+        let max_attachments = version::get_opengl_limits().max_color_attachments.min(32);
+        if index as usize >= max_attachments {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Colour attachment {} exceeds the limit of {} attachments.", index, max_attachments)
+            ));
+        }
+
+        self.bind();
+        unsafe{
+            gl_function(|| gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0 + index,
+                target,
+                texture.index(),
+                0
+            ));
+        }
+        self.color_attachments.set(self.color_attachments.get() | (1 << index));
+        Ok(())
+    }
+
+    /// Selects the colour attachments fragment shader outputs are written to - 'glDrawBuffers'
+    ///
+    /// Output 'n' is written to 'indices[n]', e.g. '&[0, 1, 2]' for a G-buffer with three targets.
+    /// Leaves this framebuffer bound
+    pub fn set_draw_buffers(&self, indices: &[u32]) -> Result<()> {
+        let max_draw_buffers = version::get_opengl_limits().max_draw_buffers;
+        if indices.len() > max_draw_buffers {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} draw buffers exceeds the limit of {}.", indices.len(), max_draw_buffers)
+            ));
+        }
+
+        let buffers = indices.iter()
+            .map(|&index| self.check_attached(index).map(|()| gl::COLOR_ATTACHMENT0 + index))
+            .collect::<Result<Vec<GLenum>>>()?;
+
+        self.bind();
+        unsafe{ gl_function(|| gl::DrawBuffers(buffers.len() as _, buffers.as_ptr())) };
+        Ok(())
+    }
+
+    /// Selects the colour attachment read by 'read_pixels' and blits - 'glReadBuffer'
+    ///
+    /// Leaves this framebuffer bound
+    pub fn set_read_buffer(&self, index: u32) -> Result<()> {
+        self.check_attached(index)?;
+
+        self.bind();
+        unsafe{ gl_function(|| gl::ReadBuffer(gl::COLOR_ATTACHMENT0 + index)) };
+        Ok(())
+    }
+
+    /// Clears a single draw buffer to 'color' - 'glClearBufferfv(GL_COLOR, ...)'
+    ///
+    /// Unlike 'clear' each target can have its own value, e.g. albedo and normals in a G-buffer.
+    /// 'draw_buffer' indexes the list given to 'set_draw_buffers', which is the colour attachment
+    /// with the same index unless remapped. Leaves this framebuffer bound
+    pub fn clear_attachment(&self, draw_buffer: u32, color: Color) -> Result<()> {
+        let max_draw_buffers = version::get_opengl_limits().max_draw_buffers;
+        if draw_buffer as usize >= max_draw_buffers {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Draw buffer {} exceeds the limit of {}.", draw_buffer, max_draw_buffers)
+            ));
+        }
+
+        let color = color.to_array();
+        self.bind();
+        unsafe{ gl_function(|| gl::ClearBufferfv(gl::COLOR, draw_buffer as _, color.as_ptr())) };
+        Ok(())
+    }
+
+    /// Clears the depth attachment - 'glClearBufferfv(GL_DEPTH, ...)'
+    ///
+    /// Leaves this framebuffer bound
+    pub fn clear_depth(&self, depth: f32) {
+        self.bind();
+        unsafe{ gl_function(|| gl::ClearBufferfv(gl::DEPTH, 0, &depth)) };
+    }
+
+    /// Clears the stencil attachment - 'glClearBufferiv(GL_STENCIL, ...)'
+    ///
+    /// Leaves this framebuffer bound
+    pub fn clear_stencil(&self, stencil: i32) {
+        self.bind();
+        unsafe{ gl_function(|| gl::ClearBufferiv(gl::STENCIL, 0, &stencil)) };
+    }
+
+    /// Clears a combined depth and stencil attachment in one call - 'glClearBufferfi'
+    ///
+    /// Leaves this framebuffer bound
+    pub fn clear_depth_stencil(&self, depth: f32, stencil: i32) {
+        self.bind();
+        unsafe{ gl_function(|| gl::ClearBufferfi(gl::DEPTH_STENCIL, 0, depth, stencil)) };
+    }
+
+    /// Copies the colour buffer's bottom-left 'width' x 'height' pixels to 'destination', or the
+    /// default framebuffer if 'None' - 'glBlitFramebuffer'
+    ///
+    /// Copying from a multisampled attachment averages the samples, resolving MSAA rendering
+    /// to a regular texture or the window; both framebuffers must then be at least this size.
+    /// Leaves this framebuffer bound for reading and 'destination' for drawing
+    pub fn blit_to(&self, destination: Option<&Framebuffer>, width: u32, height: u32) {
+        let destination = destination.map_or(0, |framebuffer| framebuffer.resource.handle().index());
+        let (width, height) = (width as GLint, height as GLint);
+
+        unsafe{
+            gl_function(|| gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.resource.handle().index()));
+            gl_function(|| gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, destination));
+            gl_function(|| gl::BlitFramebuffer(
+                0, 0, width, height,
+                0, 0, width, height,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST
+            ));
+        }
+    }
+
+    fn check_attached(&self, index: u32) -> Result<()> {
+        if index < 32 && self.color_attachments.get() & (1 << index) != 0 {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::InvalidInput, format!("Colour attachment {} has no texture attached.", index)))
+        }
+    }
+
+    /// Returns true if this framebuffer is complete and can be rendered to
+    ///
+    /// Leaves this framebuffer bound
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.bind();
+        let mut status = 0;
+        unsafe{ gl_function(|| status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER)) };
+        status == gl::FRAMEBUFFER_COMPLETE
+    }
+
+    #[must_use] pub fn resource(&self) -> &FBOResource { &self.resource }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self { Self::new() }
+}
\ No newline at end of file
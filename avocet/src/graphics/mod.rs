@@ -1,17 +1,86 @@
 mod shader;
 mod buffers;
+mod color;
+mod framebuffer;
+mod query;
+mod sampler;
+mod screenshot;
+mod state;
+mod sync;
+mod texture;
+mod uniform;
 
-use gl::types::GLuint;
+pub mod debug;
+
+use crate::{validation::try_gl_function, version};
+
+use gl::types::{GLenum, GLuint};
 
 pub use shader::*;
 pub use buffers::*;
+pub use color::*;
+pub use framebuffer::*;
+pub use query::*;
+pub use sampler::*;
+pub use screenshot::*;
+pub use state::*;
+pub use sync::*;
+pub use texture::*;
+pub use uniform::UniformLocation;
+use uniform::UniformCache;
 
+/// The name of a GL object, held by exactly one RAII resource which deletes it on drop - unless
+/// it was wrapped with 'from_raw_unowned'
+///
+/// Deliberately neither 'Clone' nor 'Copy', and only constructible within 'graphics', so a
+/// handle can't outlive or be deleted twice by its owner. Use 'raw' for identity comparisons.
 #[repr(transparent)]
 #[derive(Debug, PartialEq, Eq)]
-pub struct ResourceHandle(pub(crate) GLuint);
+pub struct ResourceHandle(GLuint);
 
 impl ResourceHandle {
     #[must_use] pub fn index(&self) -> GLuint { self.0 }
+
+    /// A non-owning copy of the handle, e.g. to compare against 'current_program'
+    #[must_use] pub fn raw(&self) -> RawHandle { RawHandle(self.0) }
+
+    /// Names the object in graphics debuggers such as RenderDoc - 'glObjectLabel'
+    ///
+    /// Requires OpenGL 4.3 or 'GL_KHR_debug', otherwise this does nothing. Labels are only a
+    /// debugging aid, so failing to apply one (e.g. the object doesn't exist yet) is just logged.
+    pub(crate) fn set_label(&self, identifier: GLenum, label: &str) {
+        if !object_labels_supported() {
+            return;
+        }
+
+        let result = unsafe{
+            try_gl_function(|| gl::ObjectLabel(identifier, self.0, label.len() as _, label.as_ptr() as _))
+        };
+
+        if let Err(error) = result {
+            log::debug!("Unable to label object {} as {:?}: {}", self.0, label, error);
+        }
+    }
+}
+
+/// A copyable, non-owning GL object name for identity comparisons - it never deletes anything
+///
+/// The object it names may have been deleted, and the name since reused. 'index' passes the
+/// name to other GL code or C libraries, which mustn't delete it while its owner is alive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RawHandle(GLuint);
+
+impl RawHandle {
+    #[must_use] pub fn index(self) -> GLuint { self.0 }
+}
+
+impl PartialEq<GLuint> for RawHandle {
+    fn eq(&self, other: &GLuint) -> bool { self.0 == *other }
+}
+
+fn object_labels_supported() -> bool {
+    let version = version::get_opengl_version();
+    gl::ObjectLabel::is_loaded() && (version.at_least(4, 3) || version::has_extension("GL_KHR_debug"))
 }
 
 // static_assert to ensure ResourceHandle and GLuint are the same size
@@ -0,0 +1,81 @@
+use crate::{
+    graphics::{ResourceHandle, VertexResource, VertexResourceLifecycle},
+    validation::gl_function,
+};
+
+use std::cell::Cell;
+
+use gl::types::*;
+
+pub struct QueryLifecycle;
+
+impl VertexResourceLifecycle for QueryLifecycle {
+    const IDENTIFIER: GLenum = gl::QUERY;
+
+    fn generate<const N: usize>() -> [ResourceHandle; N] {
+        let mut result = [const { ResourceHandle(0) }; N];
+        unsafe{ gl_function(|| gl::GenQueries(N as _, result.as_mut_ptr() as _)) };
+        result
+    }
+
+    fn destroy(handles: &[ResourceHandle]) {
+        unsafe{ gl_function(|| gl::DeleteQueries(handles.len() as _, handles.as_ptr() as _)) };
+    }
+}
+
+pub type QueryResource = VertexResource<1, QueryLifecycle>;
+
+// ------------------------------------------------------------------------------------------
+
+/// Measures how long the GPU spends on the commands between 'begin' and 'end' - 'GL_TIME_ELAPSED'
+///
+/// The result arrives asynchronously, typically a frame or two later, so poll 'elapsed_ns'
+/// rather than waiting on it. Only one timer query can be active at a time.
+pub struct TimerQuery {
+    resource: QueryResource,
+    /// True once a query has ended, before which there's no result to ask for
+    ended: Cell<bool>,
+}
+
+impl TimerQuery {
+    pub fn new() -> Self {
+        Self { resource: QueryResource::new(), ended: Cell::new(false) }
+    }
+
+    /// Starts timing, discarding any previous result - 'glBeginQuery'
+    pub fn begin(&self) {
+        unsafe{ gl_function(|| gl::BeginQuery(gl::TIME_ELAPSED, self.resource.handle().index())) };
+        self.ended.set(false);
+    }
+
+    /// Stops timing - 'glEndQuery'
+    pub fn end(&self) {
+        unsafe{ gl_function(|| gl::EndQuery(gl::TIME_ELAPSED)) };
+        self.ended.set(true);
+    }
+
+    /// The GPU time in nanoseconds between 'begin' and 'end', or 'None' until the result is ready
+    #[must_use]
+    pub fn elapsed_ns(&self) -> Option<u64> {
+        if !self.ended.get() {
+            return None;
+        }
+
+        let query = self.resource.handle().index();
+        let mut available = 0;
+        unsafe{ gl_function(|| gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available)) };
+        if available == 0 {
+            return None;
+        }
+
+        let mut elapsed = 0;
+        unsafe{ gl_function(|| gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut elapsed)) };
+        Some(elapsed)
+    }
+
+    #[must_use] pub fn resource(&self) -> &QueryResource { &self.resource }
+}
+
+impl Default for TimerQuery {
+    fn default() -> Self { Self::new() }
+}
\ No newline at end of file
@@ -0,0 +1,93 @@
+use crate::{
+    graphics::{check_mag_filter, RawHandle, ResourceHandle, TextureFilter, TextureWrap, VertexResource, VertexResourceLifecycle},
+    validation::gl_function,
+    version,
+};
+
+use std::io::{Error, ErrorKind, Result};
+
+use gl::types::*;
+
+pub struct SamplerLifecycle;
+
+impl VertexResourceLifecycle for SamplerLifecycle {
+    const IDENTIFIER: GLenum = gl::SAMPLER;
+
+    fn generate<const N: usize>() -> [ResourceHandle; N] {
+        let mut result = [const { ResourceHandle(0) }; N];
+        unsafe{ gl_function(|| gl::GenSamplers(N as _, result.as_mut_ptr() as _)) };
+        result
+    }
+
+    fn destroy(handles: &[ResourceHandle]) {
+        unsafe{ gl_function(|| gl::DeleteSamplers(handles.len() as _, handles.as_ptr() as _)) };
+    }
+
+    fn create_object(handle: &ResourceHandle) {
+        // Any parameter call creates the object, without needing a unit to bind it to
+        unsafe{ gl_function(|| gl::SamplerParameteri(handle.index(), gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint)) };
+    }
+}
+
+pub type SamplerResource = VertexResource<1, SamplerLifecycle>;
+
+// ------------------------------------------------------------------------------------------
+
+/// Filtering and wrap state independent of any texture - requires OpenGL 3.3
+///
+/// While bound to a texture unit its parameters replace those of the texture on that unit,
+/// so the same texture can be sampled differently, e.g. 'Nearest' for a pixel-art preview and
+/// 'Linear' elsewhere. New samplers use linear filtering, matching 'Texture2D'.
+pub struct Sampler {
+    resource: SamplerResource,
+}
+
+impl Sampler {
+    pub fn new() -> Result<Self> {
+        let version = version::get_opengl_version();
+        if !version.at_least(3, 3) {
+            return Err(Error::new(ErrorKind::Unsupported, "Sampler objects require OpenGL 3.3."));
+        }
+
+        let sampler = Self { resource: SamplerResource::new() };
+        sampler.set_filter(TextureFilter::Linear, TextureFilter::Linear);
+        Ok(sampler)
+    }
+
+    /// 'glSamplerParameteri' with 'GL_TEXTURE_MIN_FILTER' and 'GL_TEXTURE_MAG_FILTER'
+    pub fn set_filter(&self, min: TextureFilter, mag: TextureFilter) {
+        check_mag_filter(mag);
+
+        self.set_parameter(gl::TEXTURE_MIN_FILTER, min as GLint);
+        self.set_parameter(gl::TEXTURE_MAG_FILTER, mag as GLint);
+    }
+
+    /// Sets how all three texture coordinates wrap, so the sampler suits 3D textures too
+    pub fn set_wrap(&self, wrap: TextureWrap) {
+        for coordinate in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+            self.set_parameter(coordinate, wrap as GLint);
+        }
+    }
+
+    /// Overrides the parameters of whichever texture is bound to 'unit' - 'glBindSampler'
+    pub fn bind(&self, unit: u32) {
+        unsafe{ gl_function(|| gl::BindSampler(unit, self.resource.handle().index())) };
+    }
+
+    /// Removes any sampler from 'unit', so textures bound there use their own parameters again
+    pub fn unbind(unit: u32) {
+        unsafe{ gl_function(|| gl::BindSampler(unit, 0)) };
+    }
+
+    /// Names the sampler in graphics debuggers - see 'VertexResource::set_label'
+    pub fn set_label(&self, label: &str) { self.resource.set_label(label); }
+
+    #[must_use] pub fn resource(&self) -> &SamplerResource { &self.resource }
+
+    /// The sampler's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+
+    fn set_parameter(&self, parameter: GLenum, value: GLint) {
+        unsafe{ gl_function(|| gl::SamplerParameteri(self.resource.handle().index(), parameter, value)) };
+    }
+}
\ No newline at end of file
@@ -0,0 +1,66 @@
+use crate::{
+    graphics::{clear, ClearState, Color, Framebuffer, PackedRows},
+    validation::gl_function,
+};
+
+use std::{
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Reads a region of the currently bound read framebuffer as tightly packed RGBA8 - 'glReadPixels'
+///
+/// Note: OpenGL's origin is the bottom-left, so the first row returned is the bottom of the region
+pub fn read_pixels(x: i32, y: i32, width: u32, height: u32) -> Vec<u8> {
+    let mut pixels: Vec<u8> = vec![0; width as usize * height as usize * 4];
+
+    // With a pack alignment of 8, odd widths would be padded and overrun the buffer
+    let _packed_rows = PackedRows::for_readback(width as usize * 4);
+    unsafe{
+        gl_function(|| gl::ReadPixels(
+            x,
+            y,
+            width as _,
+            height as _,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as _
+        ));
+    }
+
+    pixels
+}
+
+/// Clears the default framebuffer to 'color' and reads back the pixel at ('x', 'y')
+///
+/// The simplest check that a context renders at all, e.g. as a smoke test of a new setup.
+/// The result is quantised to the framebuffer's precision, so compare with a small tolerance
+pub fn clear_and_read(color: Color, x: i32, y: i32) -> Color {
+    Framebuffer::bind_default();
+    clear(&ClearState::color(color));
+
+    let pixel = read_pixels(x, y, 1, 1);
+    Color::from_rgba8([pixel[0], pixel[1], pixel[2], pixel[3]])
+}
+
+/// Saves the bottom-left 'width' x 'height' region of the currently bound read framebuffer
+/// (the back buffer, unless a framebuffer object is bound) as a PNG
+///
+/// Call before swapping buffers, as the back buffer's contents are undefined afterwards
+pub fn save_screenshot<P: AsRef<Path>>(path: P, width: u32, height: u32) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(Error::new(ErrorKind::InvalidInput, "Screenshots must be at least 1x1 pixels."));
+    }
+
+    let pixels = read_pixels(0, 0, width, height);
+    let mut image = image::RgbaImage::from_raw(width, height, pixels)
+        .expect("read_pixels returns 'width' x 'height' RGBA8 pixels");
+
+    // Flip the rows so the image is top-down, as image formats expect
+    image::imageops::flip_vertical_in_place(&mut image);
+
+    image.save_with_format(path, image::ImageFormat::Png).map_err(|error| match error {
+        image::ImageError::IoError(error) => error,
+        error => Error::other(error),
+    })
+}
\ No newline at end of file
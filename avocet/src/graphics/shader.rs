@@ -1,20 +1,35 @@
 use crate::{
-    graphics::ResourceHandle,
-    validation::gl_function,
+    graphics::{memory_barrier, MemoryBarrier, RawHandle, ResourceHandle, UniformCache, VertexResource, VertexResourceLifecycle},
+    validation::{self, gl_function, try_gl_function, GlError},
+    version,
 };
 
 use std::{
+    ffi::CString,
     io::{Result, Error, ErrorKind},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use gl::types::*;
 
-#[derive(Debug, Clone, Copy)]
+/// A programmable stage of the pipeline, mapping to its 'GL_*_SHADER' type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u32)]
-enum ShaderStage {
+pub enum ShaderStage {
     Vertex = gl::VERTEX_SHADER,
-    Fragment = gl::FRAGMENT_SHADER
+    Fragment = gl::FRAGMENT_SHADER,
+    Compute = gl::COMPUTE_SHADER,
+}
+
+impl ShaderStage {
+    /// The stage's bit for 'glUseProgramStages'
+    const fn bit(self) -> GLbitfield {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER_BIT,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER_BIT,
+            ShaderStage::Compute => gl::COMPUTE_SHADER_BIT,
+        }
+    }
 }
 
 #[derive(PartialEq, Eq)]
@@ -22,8 +37,12 @@ struct ShaderCompiler(ShaderResource);
 
 impl ShaderCompiler {
     fn new(stage: ShaderStage, path: &Path) -> Result<Self> {
-        let source = std::fs::read_to_string(path)?;
-        let resource = ShaderResource::new(stage);
+        let source = read_source(stage, path)?;
+        ShaderCompiler::from_source(stage, &source)
+    }
+
+    fn from_source(stage: ShaderStage, source: &str) -> Result<Self> {
+        let resource = ShaderResource::new(stage)?;
         let shader = resource.handle().index();
         
         unsafe{
@@ -33,8 +52,8 @@ impl ShaderCompiler {
         };
 
         if let Err(error) = check_build_success(&resource) {
-            eprint!("{}", error);
-            Err(Error::new(ErrorKind::InvalidData, format!("Failed to build resource ({:?} shader).", stage)))
+            log::error!("{}", error);
+            Err(Error::new(ErrorKind::InvalidData, format!("Failed to build resource ({:?} shader).\n{}", stage, error)))
         } else {
             Ok(Self(resource))
         }
@@ -45,46 +64,441 @@ impl ShaderCompiler {
 
 // ------------------------------------------------------------------------------------------
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct ShaderProgram(ShaderProgramResource);
+/// A linked program, whose uniforms are set while it's bound - the setters don't bind it
+#[derive(Debug)]
+pub struct ShaderProgram {
+    resource: ShaderProgramResource,
+    pub(super) uniforms: UniformCache,
+}
+
+impl PartialEq for ShaderProgram {
+    fn eq(&self, other: &Self) -> bool { self.resource == other.resource }
+}
+
+impl Eq for ShaderProgram {}
 
 impl ShaderProgram {
+    fn from_resource(resource: ShaderProgramResource) -> Self {
+        Self { resource, uniforms: UniformCache::default() }
+    }
+
+    /// Wraps a linked program created by other code, e.g. a C library, without taking ownership:
+    /// dropping the 'ShaderProgram' doesn't delete it
+    ///
+    /// # Safety
+    /// 'handle' must name a successfully linked program which its owner keeps alive for as long
+    /// as the 'ShaderProgram' is used. Avocet never deletes it, so the owner remains responsible
+    pub unsafe fn from_raw_unowned(handle: GLuint) -> Self {
+        Self::from_resource(ShaderProgramResource(ResourceHandle(handle), false))
+    }
+
+    /// Compiles and links a program from vertex and fragment shader files
+    ///
+    /// Equivalent to building a 'ShaderProgramBuilder' without any attribute bindings
     pub fn new<P: AsRef<Path>>(vertex_path: P, fragment_path: P) -> Result<Self> {
-        let vertex_shader = ShaderCompiler::new(ShaderStage::Vertex, vertex_path.as_ref())?;
-        let fragment_shader = ShaderCompiler::new(ShaderStage::Fragment, fragment_path.as_ref())?;
+        ShaderProgramBuilder::new(vertex_path, fragment_path).build()
+    }
 
-        let program = Self(ShaderProgramResource::new());
-        let program_index = program.resource().handle().index();
+    /// Compiles and links a program from vertex and fragment shader source code
+    pub fn from_source(vertex_source: &str, fragment_source: &str) -> Result<Self> {
+        ShaderProgramBuilder::from_source(vertex_source, fragment_source).build()
+    }
+
+    /// Compiles and links a program from source code without a '#version' directive, prepending
+    /// '#version {version_header}' to both stages, e.g. "330 core" or "300 es"
+    ///
+    /// With 'None' the header matches the context's version, see 'version::glsl_version_string'.
+    /// Useful for embedded or generated shaders which must work across several versions.
+    pub fn from_source_with_version(vertex_source: &str, fragment_source: &str, version_header: Option<&str>) -> Result<Self> {
+        let version_header = version_header.map_or_else(
+            || version::glsl_version_string(version::get_opengl_version()),
+            str::to_owned
+        );
+
+        // '#line' keeps compile errors reporting the line numbers of the original source
+        let prefix = format!("#version {}\n#line 1\n", version_header);
+        Self::from_source(&(prefix.clone() + vertex_source), &(prefix + fragment_source))
+    }
+
+    /// Compiles and links a program from source code with '#define KEY VALUE' lines injected
+    /// into both stages, so one source can produce several variants of a shader
+    pub fn from_source_with_defines(vertex_source: &str, fragment_source: &str, defines: &[(&str, &str)]) -> Result<Self> {
+        let mut builder = ShaderProgramBuilder::from_source(vertex_source, fragment_source);
+        for (key, value) in defines {
+            builder.define(key, value);
+        }
+        builder.build()
+    }
+
+    /// Compiles and links a program from any set of stages, e.g.
+    /// '&[(ShaderStage::Vertex, "shader.vert"), (ShaderStage::Fragment, "shader.frag")]'
+    ///
+    /// Every stage is compiled before failures are reported, as with 'ShaderProgramBuilder::build'.
+    /// Each stage may only be given once.
+    pub fn from_stages<P: AsRef<Path>>(stages: &[(ShaderStage, P)]) -> Result<Self> {
+        for (index, (stage, _)) in stages.iter().enumerate() {
+            if stages[..index].iter().any(|(previous, _)| previous == stage) {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("The {:?} stage was given more than once.", stage)));
+            }
+        }
+
+        let mut shaders = Vec::with_capacity(stages.len());
+        let mut error: Option<Error> = None;
+        for (stage, path) in stages {
+            match ShaderCompiler::new(*stage, path.as_ref()) {
+                Ok(shader) => shaders.push(shader),
+                Err(stage_error) => error = Some(match error {
+                    Some(error) => combine_errors(error, stage_error),
+                    None => stage_error,
+                }),
+            }
+        }
+
+        if let Some(error) = error {
+            return Err(error);
+        }
+
+        let program = Self::from_resource(ShaderProgramResource::new()?);
+        let program_index = program.index();
 
         {
-            let _vertex_attacher = ShaderAttacher::new(&program, &vertex_shader);
-            let _fragment_attacher = ShaderAttacher::new(&program, &fragment_shader);
+            let _attachers: Vec<ShaderAttacher> = shaders.iter()
+                .map(|shader| ShaderAttacher::new(program.resource(), shader))
+                .collect();
+            request_retrievable_binary(program_index);
             unsafe{ gl_function(|| gl::LinkProgram(program_index)); }
         }
 
         if let Err(error) = check_build_success(program.resource()) {
-            eprint!("{}", error);
+            log::error!("{}", error);
             Err(Error::new(ErrorKind::InvalidData, "Failed to build resource."))
         } else {
             Ok(program)
         }
     }
 
-    #[must_use] fn resource(&self) -> &ShaderProgramResource { return &self.0; }
+    /// Compiles a single stage into a program which can be combined with others in a
+    /// 'ProgramPipeline' - 'GL_PROGRAM_SEPARABLE'. Requires OpenGL 4.1
+    ///
+    /// Outputs of one stage are matched to inputs of the next by location, so separable
+    /// shaders should use 'layout(location = n)' on their interface variables
+    pub fn separable<P: AsRef<Path>>(stage: ShaderStage, path: P) -> Result<Self> {
+        if !separable_programs_supported() {
+            return Err(Error::new(ErrorKind::Unsupported, "Separable programs require OpenGL 4.1."));
+        }
+
+        let shader = ShaderCompiler::new(stage, path.as_ref())?;
+
+        let program = Self::from_resource(ShaderProgramResource::new()?);
+        let program_index = program.index();
+
+        {
+            let _attacher = ShaderAttacher::new(program.resource(), &shader);
+            request_retrievable_binary(program_index);
+            unsafe{
+                gl_function(|| gl::ProgramParameteri(program_index, gl::PROGRAM_SEPARABLE, gl::TRUE as _));
+                gl_function(|| gl::LinkProgram(program_index));
+            }
+        }
+
+        if let Err(error) = check_build_success(program.resource()) {
+            log::error!("{}", error);
+            Err(Error::new(ErrorKind::InvalidData, format!("Failed to build resource ({:?} separable program).", stage)))
+        } else {
+            Ok(program)
+        }
+    }
+
+    pub fn builder<P: AsRef<Path>>(vertex_path: P, fragment_path: P) -> ShaderProgramBuilder {
+        ShaderProgramBuilder::new(vertex_path, fragment_path)
+    }
+
+    /// Loads a program previously written by 'save_binary', skipping compilation
+    ///
+    /// Falls back to compiling the shader sources if the binary can't be loaded, for
+    /// example when the driver has been updated and no longer accepts the binary format
+    pub fn from_binary<P: AsRef<Path>>(binary_path: P, vertex_path: P, fragment_path: P) -> Result<Self> {
+        match Self::load_binary(binary_path.as_ref()) {
+            Ok(program) => Ok(program),
+            Err(error) => {
+                log::debug!("Unable to load program binary, compiling from source: {}", error);
+                Self::new(vertex_path, fragment_path)
+            }
+        }
+    }
+
+    /// Writes the linked program binary, prefixed by its format, to the given path
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if !program_binary_supported() {
+            return Err(Error::new(ErrorKind::Unsupported, "Program binaries are not supported by this context."));
+        }
+
+        let program_index = self.resource().handle().index();
+        let length = get_parameter_value(self.resource(), gl::PROGRAM_BINARY_LENGTH);
+
+        let mut binary: Vec<u8> = vec![0; length as usize];
+        let mut written = 0;
+        let mut format = 0;
+        unsafe{
+            gl_function(|| gl::GetProgramBinary(program_index, length, &mut written, &mut format, binary.as_mut_ptr() as _));
+        }
+        binary.truncate(written as usize);
+
+        let mut contents = Vec::with_capacity(std::mem::size_of::<GLenum>() + binary.len());
+        contents.extend_from_slice(&format.to_le_bytes());
+        contents.extend_from_slice(&binary);
+
+        std::fs::write(path, contents)
+    }
+
+    fn load_binary(path: &Path) -> Result<Self> {
+        if !program_binary_supported() {
+            return Err(Error::new(ErrorKind::Unsupported, "Program binaries are not supported by this context."));
+        }
+
+        let contents = std::fs::read(path)?;
+        if contents.len() <= std::mem::size_of::<GLenum>() {
+            return Err(Error::new(ErrorKind::InvalidData, "Program binary is truncated."));
+        }
+
+        let (format, binary) = contents.split_at(std::mem::size_of::<GLenum>());
+        let format = GLenum::from_le_bytes([format[0], format[1], format[2], format[3]]);
+
+        // An unrecognised format raises 'GL_INVALID_ENUM', so check before handing it to the driver
+        if !program_binary_formats().contains(&format) {
+            return Err(Error::new(ErrorKind::InvalidData, "Program binary format is not supported by the driver."));
+        }
+
+        let program = Self::from_resource(ShaderProgramResource::new()?);
+        let program_index = program.resource().handle().index();
+        unsafe{
+            gl_function(|| gl::ProgramBinary(program_index, format, binary.as_ptr() as _, binary.len() as _));
+        }
+
+        // The driver may still reject a binary (e.g. after an update), which is reported as a link failure
+        match check_build_success(program.resource()) {
+            Ok(()) => Ok(program),
+            Err(error) => Err(Error::new(ErrorKind::InvalidData, error)),
+        }
+    }
+
+    #[must_use] fn resource(&self) -> &ShaderProgramResource { return &self.resource; }
+
+    #[must_use] pub(super) fn index(&self) -> GLuint { self.resource.handle().index() }
+
+    /// The program's name, to pass to other GL code or C libraries
+    ///
+    /// Whether dropping the program deletes the name depends on how it was made: programs Avocet
+    /// created own it, those wrapped with 'from_raw_unowned' never delete it. Either way the caller
+    /// mustn't delete the name, nor use it once its owner has. The same holds for every 'raw_handle'
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+
+    /// Whether the handle still names a program - 'glIsProgram'
+    ///
+    /// A cheap check before binding, e.g. after a failed reload or a context reset
+    #[must_use]
+    pub fn is_valid(&self) -> bool { is_valid(self.resource()) }
+
+    /// Names the program in graphics debuggers such as RenderDoc - 'glObjectLabel'
+    ///
+    /// Requires OpenGL 4.3 or 'GL_KHR_debug', otherwise this does nothing
+    pub fn set_label(&self, label: &str) {
+        self.resource.handle().set_label(gl::PROGRAM, label);
+    }
 
     pub fn bind(&self) {
-        unsafe{ gl_function(|| gl::UseProgram(self.0.handle().index())); }
+        assert_ne!(self.index(), 0, "Attempted to bind an invalid shader program");
+        unsafe{ gl_function(|| gl::UseProgram(self.index())); }
+    }
+
+    /// Binds the program, returning any error (e.g. from a deleted program) rather than panicking
+    pub fn try_bind(&self) -> std::result::Result<(), GlError> {
+        unsafe{ try_gl_function(|| gl::UseProgram(self.index())) }
+    }
+}
+
+enum ShaderSource {
+    File(PathBuf),
+    Code(String),
+}
+
+impl ShaderSource {
+    fn compile(&self, stage: ShaderStage, defines: &[(String, String)]) -> Result<ShaderCompiler> {
+        if defines.is_empty() {
+            return match self {
+                ShaderSource::File(path) => ShaderCompiler::new(stage, path),
+                ShaderSource::Code(source) => ShaderCompiler::from_source(stage, source),
+            };
+        }
+
+        let source = match self {
+            ShaderSource::File(path) => read_source(stage, path)?,
+            ShaderSource::Code(source) => source.clone(),
+        };
+        ShaderCompiler::from_source(stage, &inject_defines(&source, defines))
     }
 }
 
+/// Reads a shader file, keeping the error's kind (e.g. 'NotFound') so callers can tell failures apart
+///
+/// Invalid UTF-8 (e.g. a stray byte in a comment) is replaced rather than failing the read,
+/// any genuine problem is then reported by the compiler along with its line number
+fn read_source(stage: ShaderStage, path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)
+        .map_err(|error| Error::new(error.kind(), format!("Unable to read {:?} shader {:?}: {}", stage, path, error)))?;
+
+    match String::from_utf8(bytes) {
+        Ok(source) => Ok(source),
+        Err(error) => {
+            log::warn!("{:?} shader {:?} isn't valid UTF-8, invalid bytes have been replaced", stage, path);
+            Ok(String::from_utf8_lossy(error.as_bytes()).into_owned())
+        }
+    }
+}
+
+/// Inserts '#define KEY VALUE' lines after the '#version' directive, which GLSL requires to
+/// come first. A '#line' directive follows the defines so compile errors keep their line numbers.
+pub(crate) fn inject_defines(source: &str, defines: &[(String, String)]) -> String {
+    let mut lines: Vec<&str> = source.lines().collect();
+    let insert_at = lines.iter()
+        .position(|line| line.trim_start().starts_with("#version"))
+        .map_or(0, |version_line| version_line + 1);
+
+    let define_lines: Vec<String> = defines.iter()
+        .map(|(key, value)| format!("#define {} {}", key, value))
+        .chain(std::iter::once(format!("#line {}", insert_at + 1)))
+        .collect();
+
+    lines.splice(insert_at..insert_at, define_lines.iter().map(String::as_str));
+    lines.join("\n")
+}
+
+/// Builds a 'ShaderProgram' in stages: compile and attach the shaders, bind any
+/// attribute locations and finally link
+pub struct ShaderProgramBuilder {
+    vertex: ShaderSource,
+    fragment: ShaderSource,
+    attrib_locations: Vec<(String, GLuint)>,
+    defines: Vec<(String, String)>,
+}
+
+impl ShaderProgramBuilder {
+    pub fn new<P: AsRef<Path>>(vertex_path: P, fragment_path: P) -> Self {
+        Self {
+            vertex: ShaderSource::File(vertex_path.as_ref().to_path_buf()),
+            fragment: ShaderSource::File(fragment_path.as_ref().to_path_buf()),
+            attrib_locations: Vec::new(),
+            defines: Vec::new(),
+        }
+    }
+
+    pub fn from_source(vertex_source: &str, fragment_source: &str) -> Self {
+        Self {
+            vertex: ShaderSource::Code(vertex_source.to_owned()),
+            fragment: ShaderSource::Code(fragment_source.to_owned()),
+            attrib_locations: Vec::new(),
+            defines: Vec::new(),
+        }
+    }
+
+    /// Binds the named vertex attribute to a location before linking - 'glBindAttribLocation'
+    ///
+    /// Only required for shaders which don't specify 'layout(location = n)' qualifiers
+    pub fn bind_attrib_location(&mut self, name: &str, location: GLuint) -> &mut Self {
+        self.attrib_locations.push((name.to_owned(), location));
+        self
+    }
+
+    /// Adds '#define key value' to every stage, after the '#version' directive
+    pub fn define(&mut self, key: &str, value: &str) -> &mut Self {
+        self.defines.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Compiles every stage before reporting failures, so the error lists each failed stage
+    /// rather than stopping at the first
+    pub fn build(&self) -> Result<ShaderProgram> {
+        let (vertex_shader, fragment_shader) = match (
+            self.vertex.compile(ShaderStage::Vertex, &self.defines),
+            self.fragment.compile(ShaderStage::Fragment, &self.defines),
+        ) {
+            (Ok(vertex_shader), Ok(fragment_shader)) => (vertex_shader, fragment_shader),
+            (Err(error), Ok(_)) | (Ok(_), Err(error)) => return Err(error),
+            (Err(vertex_error), Err(fragment_error)) => return Err(combine_errors(vertex_error, fragment_error)),
+        };
+
+        let program = ShaderProgram::from_resource(ShaderProgramResource::new()?);
+        let program_index = program.resource().handle().index();
+
+        {
+            let _vertex_attacher = ShaderAttacher::new(program.resource(), &vertex_shader);
+            let _fragment_attacher = ShaderAttacher::new(program.resource(), &fragment_shader);
+
+            // Attribute bindings only take effect when the program is next linked
+            for (name, location) in &self.attrib_locations {
+                let name = CString::new(name.as_str())
+                    .map_err(|_| Error::new(ErrorKind::InvalidInput, format!("Invalid attribute name: {:?}", name)))?;
+                unsafe{ gl_function(|| gl::BindAttribLocation(program_index, *location, name.as_ptr())); }
+            }
+
+            request_retrievable_binary(program_index);
+            unsafe{ gl_function(|| gl::LinkProgram(program_index)); }
+        }
+
+        if let Err(error) = check_build_success(program.resource()) {
+            log::error!("{}", error);
+            Err(Error::new(ErrorKind::InvalidData, "Failed to build resource."))
+        } else {
+            Ok(program)
+        }
+    }
+}
+
+/// Joins the errors of several stages into one, keeping the kind of the first
+fn combine_errors(first: Error, second: Error) -> Error {
+    Error::new(first.kind(), format!("{}\n{}", first, second))
+}
+
+/// Program binaries are core since OpenGL 4.1, otherwise 'GL_ARB_get_program_binary' is required
+fn program_binary_supported() -> bool {
+    let version = version::get_opengl_version();
+    (version.at_least(4, 1) || version::has_extension("GL_ARB_get_program_binary")) && !program_binary_formats().is_empty()
+}
+
+/// Asks the driver to keep the linked binary for 'save_binary' - 'GL_PROGRAM_BINARY_RETRIEVABLE_HINT'
+///
+/// Only takes effect if set before linking, without it some drivers have no binary to return
+fn request_retrievable_binary(program_index: GLuint) {
+    if program_binary_supported() {
+        unsafe{ gl_function(|| gl::ProgramParameteri(program_index, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as _)); }
+    }
+}
+
+fn program_binary_formats() -> Vec<GLenum> {
+    let mut count = 0;
+    unsafe{ gl_function(|| gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut count)); }
+
+    let mut formats: Vec<GLint> = vec![0; count as usize];
+    if count > 0 {
+        unsafe{ gl_function(|| gl::GetIntegerv(gl::PROGRAM_BINARY_FORMATS, formats.as_mut_ptr())); }
+    }
+
+    formats.into_iter().map(|format| format as GLenum).collect()
+}
+
 struct ShaderAttacher {
     program: GLuint,
     shader: GLuint
 }
 
 impl ShaderAttacher {
-    fn new(program: &ShaderProgram, shader: &ShaderCompiler) -> Self {
-        let program = program.resource().handle().index();
+    fn new(program: &ShaderProgramResource, shader: &ShaderCompiler) -> Self {
+        if const { validation::should_validate() } && !is_valid(shader.resource()) {
+            log::error!("Attempted to attach invalid shader {}", shader.resource().handle().index());
+            panic!("Attempted to attach invalid shader {}", shader.resource().handle().index());
+        }
+
+        let program = program.handle().index();
         let shader = shader.resource().handle().index();
 
         unsafe{ gl_function(|| gl::AttachShader(program, shader)) };
@@ -101,19 +515,164 @@ impl Drop for ShaderAttacher {
 
 // ------------------------------------------------------------------------------------------
 
+/// A program consisting of a single compute shader - requires OpenGL 4.3
+#[derive(Debug, PartialEq, Eq)]
+pub struct ComputeProgram(ShaderProgramResource);
+
+impl ComputeProgram {
+    pub fn new<P: AsRef<Path>>(compute_path: P) -> Result<Self> {
+        let version = version::get_opengl_version();
+        if !version.at_least(4, 3) {
+            return Err(Error::new(ErrorKind::Unsupported, "Compute shaders require OpenGL 4.3."));
+        }
+
+        let compute_shader = ShaderCompiler::new(ShaderStage::Compute, compute_path.as_ref())?;
+
+        let program = Self(ShaderProgramResource::new()?);
+        let program_index = program.resource().handle().index();
+
+        {
+            let _compute_attacher = ShaderAttacher::new(program.resource(), &compute_shader);
+            unsafe{ gl_function(|| gl::LinkProgram(program_index)); }
+        }
+
+        if let Err(error) = check_build_success(program.resource()) {
+            log::error!("{}", error);
+            Err(Error::new(ErrorKind::InvalidData, "Failed to build resource."))
+        } else {
+            Ok(program)
+        }
+    }
+
+    #[must_use] fn resource(&self) -> &ShaderProgramResource { &self.0 }
+
+    /// The program's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.0.handle().raw() }
+
+    /// Whether the handle still names a program - 'glIsProgram'
+    #[must_use]
+    pub fn is_valid(&self) -> bool { is_valid(self.resource()) }
+
+    pub fn bind(&self) {
+        assert_ne!(self.0.handle().index(), 0, "Attempted to bind an invalid compute program");
+        unsafe{ gl_function(|| gl::UseProgram(self.0.handle().index())); }
+    }
+
+    /// Dispatches the given number of work groups, followed by a barrier on all memory
+    /// accesses - use 'dispatch_with_barrier' to only wait on what's actually consumed
+    pub fn dispatch(&self, x: u32, y: u32, z: u32) {
+        self.dispatch_with_barrier(x, y, z, MemoryBarrier::ALL);
+    }
+
+    /// Dispatches the given number of work groups - 'glDispatchCompute' - followed by a
+    /// 'glMemoryBarrier' matching how the results are subsequently read, for example
+    /// 'MemoryBarrier::SHADER_STORAGE' when a later shader reads a storage buffer
+    pub fn dispatch_with_barrier(&self, x: u32, y: u32, z: u32, barriers: MemoryBarrier) {
+        self.bind();
+        unsafe{ gl_function(|| gl::DispatchCompute(x, y, z)); }
+        memory_barrier(barriers);
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+
+pub struct ProgramPipelineLifecycle;
+
+impl VertexResourceLifecycle for ProgramPipelineLifecycle {
+    const IDENTIFIER: GLenum = gl::PROGRAM_PIPELINE;
+
+    fn generate<const N: usize>() -> [ResourceHandle; N] {
+        let mut result = [const { ResourceHandle(0) }; N];
+        unsafe{ gl_function(|| gl::GenProgramPipelines(N as _, result.as_mut_ptr() as _)) };
+        result
+    }
+
+    fn destroy(handles: &[ResourceHandle]) {
+        unsafe{ gl_function(|| gl::DeleteProgramPipelines(handles.len() as _, handles.as_ptr() as _)) };
+    }
+}
+
+pub type ProgramPipelineResource = VertexResource<1, ProgramPipelineLifecycle>;
+
+/// Combines separable programs, one per stage, into a pipeline - requires OpenGL 4.1
+///
+/// Stages can be swapped independently, e.g. one vertex program shared between several
+/// fragment programs, without relinking. A program bound with 'glUseProgram' takes precedence
+/// over the bound pipeline, so 'bind' unbinds the current program.
+pub struct ProgramPipeline {
+    resource: ProgramPipelineResource,
+}
+
+impl ProgramPipeline {
+    pub fn new() -> Result<Self> {
+        if !separable_programs_supported() {
+            return Err(Error::new(ErrorKind::Unsupported, "Program pipelines require OpenGL 4.1."));
+        }
+
+        Ok(Self { resource: ProgramPipelineResource::new() })
+    }
+
+    /// Uses the given stage of 'program' for this pipeline - 'glUseProgramStages'
+    ///
+    /// 'program' must have been created with 'ShaderProgram::separable'
+    pub fn attach(&self, stage: ShaderStage, program: &ShaderProgram) -> &Self {
+        unsafe{ gl_function(|| gl::UseProgramStages(self.index(), stage.bit(), program.index())) };
+        self
+    }
+
+    /// Removes the program used for the given stage
+    pub fn detach(&self, stage: ShaderStage) -> &Self {
+        unsafe{ gl_function(|| gl::UseProgramStages(self.index(), stage.bit(), 0)) };
+        self
+    }
+
+    /// Binds the pipeline - 'glBindProgramPipeline'
+    pub fn bind(&self) {
+        unsafe{
+            gl_function(|| gl::UseProgram(0));
+            gl_function(|| gl::BindProgramPipeline(self.index()));
+        }
+    }
+
+    /// Names the pipeline in graphics debuggers such as RenderDoc - 'glObjectLabel'
+    ///
+    /// The pipeline object only exists once a stage has been attached or it's been bound
+    pub fn set_label(&self, label: &str) {
+        self.resource.handle().set_label(ProgramPipelineLifecycle::IDENTIFIER, label);
+    }
+
+    #[must_use] pub fn resource(&self) -> &ProgramPipelineResource { &self.resource }
+
+    #[must_use] fn index(&self) -> GLuint { self.resource.handle().index() }
+}
+
+/// Separable programs and program pipelines are core since OpenGL 4.1
+fn separable_programs_supported() -> bool {
+    version::get_opengl_version().at_least(4, 1) || version::has_extension("GL_ARB_separate_shader_objects")
+}
+
+// ------------------------------------------------------------------------------------------
+
 macro_rules! shader_resource {
     (
         $struct_vis:vis struct $name:ident (ResourceHandle) {
-            $new_vis:vis fn new($($argn:ident: $argt:ty),*) -> Self { $($new_body:tt)* }
+            $new_vis:vis fn new($($argn:ident: $argt:ty),*) -> Result<Self> { $($new_body:tt)* }
             fn drop($handle:ident: &ResourceHandle) { $($drop_body:tt)* }
         }
 
     ) => {
-        #[derive(Debug, PartialEq, Eq)]
-        $struct_vis struct $name (ResourceHandle);
+        /// The flag is false for objects owned elsewhere, which aren't deleted on drop
+        #[derive(Debug)]
+        $struct_vis struct $name (ResourceHandle, bool);
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
+        }
+
+        impl Eq for $name {}
 
         impl $name {
-            $new_vis fn new($($argn: $argt),*) -> Self {
+            $new_vis fn new($($argn: $argt),*) -> Result<Self> {
                 $($new_body)*
             }
 
@@ -128,8 +687,10 @@ macro_rules! shader_resource {
 
         impl Drop for $name {
             fn drop(&mut self) {
-                let $handle: &ResourceHandle = &self.0;
-                $($drop_body)*
+                if self.1 {
+                    let $handle: &ResourceHandle = &self.0;
+                    $($drop_body)*
+                }
             }
         }
     };
@@ -137,10 +698,10 @@ macro_rules! shader_resource {
 
 shader_resource!{
     struct ShaderResource(ResourceHandle) {
-        fn new(stage: ShaderStage) -> Self {
+        fn new(stage: ShaderStage) -> Result<Self> {
             let mut id = 0;
             unsafe{ gl_function(|| id = gl::CreateShader(stage as GLenum)) };
-            Self(ResourceHandle(id))
+            check_created(id, "glCreateShader").map(|()| Self(ResourceHandle(id), true))
         }
 
         fn drop(handle: &ResourceHandle) {
@@ -149,6 +710,17 @@ shader_resource!{
     }
 }
 
+/// 'glCreateShader' and 'glCreateProgram' return 0 rather than raising an error in release, e.g.
+/// without a current context, so fail here rather than carrying on with a handle that names nothing
+fn check_created(id: GLuint, function: &str) -> Result<()> {
+    if id == 0 {
+        log::error!("'{}' returned 0", function);
+        return Err(Error::other(format!("'{}' failed to create an object, is a context current?", function)));
+    }
+
+    Ok(())
+}
+
 impl BuiltResource for ShaderResource {
     const NAME: &'static str = "shader";
     const BUILD_STAGE: &'static str = "compilation";
@@ -156,15 +728,16 @@ impl BuiltResource for ShaderResource {
 
     #[inline(always)] fn get_parameter_fn(&self) -> GetStatusFn { gl::GetShaderiv }
     #[inline(always)] fn get_info_log_fn(&self) -> GetInfoFn { gl::GetShaderInfoLog }
+    #[inline(always)] fn is_valid_fn(&self) -> IsValidFn { gl::IsShader }
 
 }
 
 shader_resource!{
     struct ShaderProgramResource(ResourceHandle) {
-        fn new() -> Self {
+        fn new() -> Result<Self> {
             let mut id = 0;
             unsafe{ gl_function(|| id = gl::CreateProgram()) };
-            Self(ResourceHandle(id))
+            check_created(id, "glCreateProgram").map(|()| Self(ResourceHandle(id), true))
         }
 
         fn drop(handle: &ResourceHandle) {
@@ -180,6 +753,7 @@ impl BuiltResource for ShaderProgramResource {
 
     #[inline(always)] fn get_parameter_fn(&self) -> GetStatusFn { gl::GetProgramiv }
     #[inline(always)] fn get_info_log_fn(&self) -> GetInfoFn { gl::GetProgramInfoLog }
+    #[inline(always)] fn is_valid_fn(&self) -> IsValidFn { gl::IsProgram }
 
 }
 
@@ -187,6 +761,7 @@ impl BuiltResource for ShaderProgramResource {
 
 type GetStatusFn = unsafe fn(GLuint, GLenum, *mut GLint);
 type GetInfoFn = unsafe fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar);
+type IsValidFn = unsafe fn(GLuint) -> GLboolean;
 
 trait BuiltResource: AsRef<ResourceHandle> {
     const NAME: &'static str;
@@ -195,6 +770,17 @@ trait BuiltResource: AsRef<ResourceHandle> {
 
     fn get_parameter_fn(&self) -> GetStatusFn;
     fn get_info_log_fn(&self) -> GetInfoFn;
+    fn is_valid_fn(&self) -> IsValidFn;
+}
+
+/// Whether the handle names an object of the resource's type - 'glIsShader'/'glIsProgram'
+///
+/// Deleted objects stay valid while still attached or in use, so this only reports false once
+/// the driver has actually released the name
+fn is_valid<T: BuiltResource>(resource: &T) -> bool {
+    let mut valid = gl::FALSE;
+    unsafe{ gl_function(|| valid = resource.is_valid_fn()(resource.as_ref().index())) };
+    valid == gl::TRUE
 }
 
 fn get_parameter_value<T: BuiltResource>(resource: &T, parameter_id: GLenum) -> GLint {
@@ -208,14 +794,16 @@ fn get_parameter_value<T: BuiltResource>(resource: &T, parameter_id: GLenum) ->
 fn get_info_log<T: BuiltResource>(resource: &T) -> String {
     let length = get_parameter_value(resource, gl::INFO_LOG_LENGTH) as usize;
 
-    let mut buffer: Vec<u8> = Vec::with_capacity(length);
-    let result = unsafe{
-        gl_function(|| resource.get_info_log_fn()(resource.as_ref().index(), length as GLsizei, std::ptr::null_mut(), buffer.as_mut_ptr() as *mut _));
-        buffer.set_len(length);
-        String::from_utf8_unchecked(buffer)
-    };
+    let mut buffer: Vec<u8> = vec![0; length];
+    let mut written = 0;
+    unsafe{
+        gl_function(|| resource.get_info_log_fn()(resource.as_ref().index(), length as GLsizei, &mut written, buffer.as_mut_ptr() as *mut _));
+    }
+    buffer.truncate(written as usize);
 
-    result
+    // The log comes from the driver, so don't trust it to be valid UTF-8. This only
+    // runs when a build fails, so the potential allocation doesn't matter
+    String::from_utf8_lossy(&buffer).into_owned()
 }
 
 fn check_build_success<T: BuiltResource>(resource: &T) -> std::result::Result<(), String> {
@@ -0,0 +1,430 @@
+use crate::{
+    graphics::Color,
+    validation::{self, gl_function},
+};
+
+use gl::types::{GLbitfield, GLenum, GLuint};
+
+fn set_capability(capability: GLenum, enabled: bool) {
+    if enabled {
+        unsafe{ gl_function(|| gl::Enable(capability)) };
+    } else {
+        unsafe{ gl_function(|| gl::Disable(capability)) };
+    }
+}
+
+/// Sets the viewport - 'glViewport'
+///
+/// The viewport is measured in framebuffer pixels, not in window (screen) coordinates.
+/// On HiDPI displays the framebuffer can be larger than the window (e.g. twice the size
+/// with a content scale of 2.0), so always pass the framebuffer size here.
+pub fn set_viewport(x: i32, y: i32, width: i32, height: i32) {
+    unsafe{ gl_function(|| gl::Viewport(x, y, width, height)) };
+}
+
+/// Enables the scissor test - 'GL_SCISSOR_TEST'
+///
+/// Whilst enabled, draws and clears only affect pixels inside the 'set_scissor' rectangle
+pub fn set_scissor_test(enabled: bool) {
+    set_capability(gl::SCISSOR_TEST, enabled);
+}
+
+/// Sets the scissor rectangle - 'glScissor'
+///
+/// Like the viewport this is measured in framebuffer pixels from the bottom-left, but unlike
+/// the viewport it clips rather than scales, so it can restrict drawing within a viewport
+pub fn set_scissor(x: i32, y: i32, width: i32, height: i32) {
+    unsafe{ gl_function(|| gl::Scissor(x, y, width, height)) };
+}
+
+/// Runs 'f' with the given viewport, then restores the previous one - even if 'f' panics
+///
+/// E.g. to render a pass into an offscreen target of a different size than the window
+pub fn with_viewport<R>(x: i32, y: i32, width: i32, height: i32, f: impl FnOnce() -> R) -> R {
+    let [previous_x, previous_y, previous_width, previous_height] = get_rectangle(gl::VIEWPORT);
+    let _restore = RestoreOnDrop(|| set_viewport(previous_x, previous_y, previous_width, previous_height));
+
+    set_viewport(x, y, width, height);
+    f()
+}
+
+/// Runs 'f' with the scissor test enabled for the given rectangle, then restores the previous
+/// rectangle and whether the test was enabled - even if 'f' panics
+pub fn with_scissor<R>(x: i32, y: i32, width: i32, height: i32, f: impl FnOnce() -> R) -> R {
+    let [previous_x, previous_y, previous_width, previous_height] = get_rectangle(gl::SCISSOR_BOX);
+    let mut was_enabled = false;
+    unsafe{ gl_function(|| was_enabled = gl::IsEnabled(gl::SCISSOR_TEST) == gl::TRUE) };
+    let _restore = RestoreOnDrop(|| {
+        set_scissor(previous_x, previous_y, previous_width, previous_height);
+        set_scissor_test(was_enabled);
+    });
+
+    set_scissor_test(true);
+    set_scissor(x, y, width, height);
+    f()
+}
+
+/// Queries a rectangle as (x, y, width, height), e.g. 'GL_VIEWPORT'
+fn get_rectangle(parameter: GLenum) -> [i32; 4] {
+    let mut rectangle = [0; 4];
+    unsafe{ gl_function(|| gl::GetIntegerv(parameter, rectangle.as_mut_ptr())) };
+    rectangle
+}
+
+struct RestoreOnDrop<F: FnMut()>(F);
+
+impl<F: FnMut()> Drop for RestoreOnDrop<F> {
+    fn drop(&mut self) { (self.0)(); }
+}
+
+/// Enables linear to sRGB conversion when writing to an sRGB capable framebuffer - 'GL_FRAMEBUFFER_SRGB'
+///
+/// Shaders should output linear colour values; with this enabled they are gamma encoded on
+/// write, matching how monitors display them. Without it, linear output appears too dark and
+/// sRGB textures (see 'Texture2D::new') end up decoded but never re-encoded.
+pub fn set_framebuffer_srgb(enabled: bool) {
+    set_capability(gl::FRAMEBUFFER_SRGB, enabled);
+}
+
+/// Enables multisample rasterisation - 'GL_MULTISAMPLE'
+///
+/// Only has an effect when the framebuffer has samples, e.g. a window created with MSAA
+pub fn set_multisample(enabled: bool) {
+    set_capability(gl::MULTISAMPLE, enabled);
+}
+
+/// The number of samples per pixel of the current framebuffer - 'GL_SAMPLES'
+///
+/// The sample count requested at window creation may be clamped by the driver, this is the actual value
+pub fn sample_count() -> u32 {
+    let mut samples = 0;
+    unsafe{ gl_function(|| gl::GetIntegerv(gl::SAMPLES, &mut samples)) };
+    samples as u32
+}
+
+/// The range of supported widths for aliased (non-antialiased) lines - 'GL_ALIASED_LINE_WIDTH_RANGE'
+#[must_use]
+pub fn line_width_range() -> (f32, f32) {
+    let mut range = [0.0f32; 2];
+    unsafe{ gl_function(|| gl::GetFloatv(gl::ALIASED_LINE_WIDTH_RANGE, range.as_mut_ptr())) };
+    (range[0], range[1])
+}
+
+/// Sets the width of rasterised lines in pixels - 'glLineWidth'
+///
+/// The width is clamped to 'line_width_range'. Wide lines are deprecated: forward compatible
+/// core contexts (required on Mac) reject widths above 1.0, so the width is clamped to 1.0 there.
+/// For thick lines in a core profile, draw them as quads instead.
+pub fn set_line_width(width: f32) {
+    let mut flags = 0;
+    unsafe{ gl_function(|| gl::GetIntegerv(gl::CONTEXT_FLAGS, &mut flags)) };
+    let forward_compatible = (flags as GLenum & gl::CONTEXT_FLAG_FORWARD_COMPATIBLE_BIT) != 0;
+
+    let (min, max) = line_width_range();
+    let max = if forward_compatible { max.min(1.0) } else { max };
+    let clamped = width.clamp(min, max.max(min));
+    if clamped != width {
+        log::debug!("Line width {} is unsupported, clamped to {}", width, clamped);
+    }
+
+    unsafe{ gl_function(|| gl::LineWidth(clamped)) };
+}
+
+/// Sets the diameter of rasterised points in pixels - 'glPointSize'
+///
+/// Ignored whilst 'set_program_point_size' is enabled, as the shader's 'gl_PointSize' is used instead
+pub fn set_point_size(size: f32) {
+    unsafe{ gl_function(|| gl::PointSize(size)) };
+}
+
+/// Lets vertex shaders set the point size by writing 'gl_PointSize' - 'GL_PROGRAM_POINT_SIZE'
+pub fn set_program_point_size(enabled: bool) {
+    set_capability(gl::PROGRAM_POINT_SIZE, enabled);
+}
+
+/// Sets the alignment in bytes (1, 2, 4 or 8) of each row of pixel data uploaded to a
+/// texture - 'GL_UNPACK_ALIGNMENT', 4 by default
+///
+/// Rows whose size isn't a multiple of the alignment are read as if padded, so tightly packed
+/// single channel or RGB images look sheared unless this is 1. Texture uploads set it as
+/// required, so this is only needed when calling 'glTexImage*' directly.
+pub fn set_unpack_alignment(alignment: u32) {
+    assert!(matches!(alignment, 1 | 2 | 4 | 8), "Unpack alignment must be 1, 2, 4 or 8, not {}", alignment);
+    unsafe{ gl_function(|| gl::PixelStorei(gl::UNPACK_ALIGNMENT, alignment as _)) };
+}
+
+/// The current 'GL_UNPACK_ALIGNMENT', see 'set_unpack_alignment'
+#[must_use]
+pub fn unpack_alignment() -> u32 {
+    let mut alignment = 0;
+    unsafe{ gl_function(|| gl::GetIntegerv(gl::UNPACK_ALIGNMENT, &mut alignment)) };
+    alignment as u32
+}
+
+/// Sets the alignment in bytes (1, 2, 4 or 8) of each row of pixel data read back from the
+/// GPU - 'GL_PACK_ALIGNMENT', 4 by default
+///
+/// The readback counterpart of 'set_unpack_alignment': rows are padded to the alignment, so
+/// the buffer must be sized for it. 'read_pixels' sets it as required.
+pub fn set_pack_alignment(alignment: u32) {
+    assert!(matches!(alignment, 1 | 2 | 4 | 8), "Pack alignment must be 1, 2, 4 or 8, not {}", alignment);
+    unsafe{ gl_function(|| gl::PixelStorei(gl::PACK_ALIGNMENT, alignment as _)) };
+}
+
+/// The current 'GL_PACK_ALIGNMENT', see 'set_pack_alignment'
+#[must_use]
+pub fn pack_alignment() -> u32 {
+    let mut alignment = 0;
+    unsafe{ gl_function(|| gl::GetIntegerv(gl::PACK_ALIGNMENT, &mut alignment)) };
+    alignment as u32
+}
+
+/// Transfers to and from the GPU use tightly packed rows, so while alive this lowers the pack or
+/// unpack alignment to 1 if rows of 'row_bytes' aren't already aligned, restoring it on drop
+pub(crate) struct PackedRows {
+    parameter: GLenum,
+    previous: Option<u32>,
+}
+
+impl PackedRows {
+    /// For texture uploads - 'GL_UNPACK_ALIGNMENT'
+    pub(crate) fn for_upload(row_bytes: usize) -> Self {
+        Self::new(gl::UNPACK_ALIGNMENT, unpack_alignment(), row_bytes)
+    }
+
+    /// For 'glReadPixels' and similar - 'GL_PACK_ALIGNMENT'
+    pub(crate) fn for_readback(row_bytes: usize) -> Self {
+        Self::new(gl::PACK_ALIGNMENT, pack_alignment(), row_bytes)
+    }
+
+    fn new(parameter: GLenum, current: u32, row_bytes: usize) -> Self {
+        if row_bytes.is_multiple_of(current as usize) {
+            return Self { parameter, previous: None };
+        }
+
+        unsafe{ gl_function(|| gl::PixelStorei(parameter, 1)) };
+        Self { parameter, previous: Some(current) }
+    }
+}
+
+impl Drop for PackedRows {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous {
+            unsafe{ gl_function(|| gl::PixelStorei(self.parameter, previous as _)) };
+        }
+    }
+}
+
+fn get_binding(binding: GLenum) -> GLuint {
+    let mut handle = 0;
+    unsafe{ gl_function(|| gl::GetIntegerv(binding, &mut handle)) };
+    handle as GLuint
+}
+
+/// Enables primitive restart - 'GL_PRIMITIVE_RESTART' and 'glPrimitiveRestartIndex'
+///
+/// Whilst enabled, an index equal to 'index' in an indexed draw ends the current strip (or fan
+/// or loop) and starts a new one, so several triangle strips can share one element buffer.
+/// Use the maximum value of the index type as the sentinel (e.g. 'u16::MAX' for 'u16'
+/// indices), as it's never a valid vertex in practice. The index is ignored when disabling.
+pub fn set_primitive_restart(enabled: bool, index: u32) {
+    set_capability(gl::PRIMITIVE_RESTART, enabled);
+    if enabled {
+        unsafe{ gl_function(|| gl::PrimitiveRestartIndex(index)) };
+    }
+}
+
+/// Unbinds the shader program and vertex array object and rebinds the default framebuffer
+///
+/// Gives a known baseline between subsystems, e.g. before handing over to another library
+/// drawing with the same context. Other state (blending, depth testing, etc) is left untouched.
+pub fn reset_state() {
+    unsafe{
+        gl_function(|| gl::UseProgram(0));
+        gl_function(|| gl::BindVertexArray(0));
+        gl_function(|| gl::BindFramebuffer(gl::FRAMEBUFFER, 0));
+    }
+}
+
+/// The raw handle of the currently bound shader program, or 0 if none - 'GL_CURRENT_PROGRAM'
+///
+/// Intended for debugging and asserting expected state, e.g. against 'ResourceHandle::index'
+#[must_use]
+pub fn current_program() -> GLuint {
+    get_binding(gl::CURRENT_PROGRAM)
+}
+
+/// The raw handle of the currently bound vertex array object, or 0 if none - 'GL_VERTEX_ARRAY_BINDING'
+#[must_use]
+pub fn current_vao() -> GLuint {
+    get_binding(gl::VERTEX_ARRAY_BINDING)
+}
+
+/// The raw handle of the buffer bound to 'GL_ARRAY_BUFFER', or 0 if none - 'GL_ARRAY_BUFFER_BINDING'
+#[must_use]
+pub fn current_array_buffer() -> GLuint {
+    get_binding(gl::ARRAY_BUFFER_BINDING)
+}
+
+/// Panics under validation if no buffer is bound to 'GL_ARRAY_BUFFER'
+///
+/// Call before 'glVertexAttribPointer', which otherwise (in a compatibility context) treats the
+/// offset as a pointer into client memory, or captures whichever buffer happened to be bound
+pub(crate) fn check_array_buffer_bound(location: u32) {
+    if const { validation::should_validate() } && current_array_buffer() == 0 {
+        log::error!("Vertex attribute {} was specified with no buffer bound to 'GL_ARRAY_BUFFER'", location);
+        panic!("No vertex buffer bound for attribute {}", location);
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// The buffers to clear and the values to clear them to, buffers which are 'None' are left untouched
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct ClearState {
+    pub color: Option<Color>,
+    /// Between 0.0 and 1.0, usually 1.0 (the far plane) with the default depth test
+    pub depth: Option<f32>,
+    pub stencil: Option<i32>,
+}
+
+impl ClearState {
+    /// Clears only the color buffer
+    pub const fn color(color: Color) -> Self {
+        Self { color: Some(color), depth: None, stencil: None }
+    }
+
+    /// Clears the color buffer and resets depth to the far plane and stencil to 0
+    pub const fn all(color: Color) -> Self {
+        Self { color: Some(color), depth: Some(1.0), stencil: Some(0) }
+    }
+}
+
+/// Clears the buffers of the current framebuffer present in 'state' with a single 'glClear'
+///
+/// Clearing respects the write masks, so set 'set_stencil_mask(!0)' first to clear all stencil bits
+pub fn clear(state: &ClearState) {
+    let mut mask: GLbitfield = 0;
+
+    if let Some(color) = state.color {
+        unsafe{ gl_function(|| gl::ClearColor(color.r, color.g, color.b, color.a)) };
+        mask |= gl::COLOR_BUFFER_BIT;
+    }
+
+    if let Some(depth) = state.depth {
+        unsafe{ gl_function(|| gl::ClearDepth(depth as _)) };
+        mask |= gl::DEPTH_BUFFER_BIT;
+    }
+
+    if let Some(stencil) = state.stencil {
+        unsafe{ gl_function(|| gl::ClearStencil(stencil)) };
+        mask |= gl::STENCIL_BUFFER_BIT;
+    }
+
+    if mask != 0 {
+        unsafe{ gl_function(|| gl::Clear(mask)) };
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// How a value is compared against the one already stored, e.g. in the stencil or depth buffer
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u32)]
+pub enum CompareFunc {
+    Never = gl::NEVER,
+    Less = gl::LESS,
+    Equal = gl::EQUAL,
+    LessOrEqual = gl::LEQUAL,
+    Greater = gl::GREATER,
+    NotEqual = gl::NOTEQUAL,
+    GreaterOrEqual = gl::GEQUAL,
+    Always = gl::ALWAYS,
+}
+
+/// What happens to the stored stencil value - see 'set_stencil_op'
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u32)]
+pub enum StencilOp {
+    Keep = gl::KEEP,
+    Zero = gl::ZERO,
+    /// Replaces the value with the reference value given to 'set_stencil_func'
+    Replace = gl::REPLACE,
+    /// Increments the value, clamping at the maximum
+    Increment = gl::INCR,
+    /// Increments the value, wrapping to 0 at the maximum
+    IncrementWrap = gl::INCR_WRAP,
+    /// Decrements the value, clamping at 0
+    Decrement = gl::DECR,
+    /// Decrements the value, wrapping to the maximum at 0
+    DecrementWrap = gl::DECR_WRAP,
+    Invert = gl::INVERT,
+}
+
+/// Enables the stencil test - 'GL_STENCIL_TEST'
+///
+/// Requires a framebuffer with a stencil buffer, e.g. a 'Renderbuffer' with 'Depth24Stencil8'
+pub fn set_stencil_test(enabled: bool) {
+    set_capability(gl::STENCIL_TEST, enabled);
+}
+
+/// Sets how fragments are tested against the stencil buffer - 'glStencilFunc'
+///
+/// A fragment passes when '(ref_value & mask) func (stored & mask)' is true
+pub fn set_stencil_func(func: CompareFunc, ref_value: i32, mask: u32) {
+    unsafe{ gl_function(|| gl::StencilFunc(func as GLenum, ref_value, mask)) };
+}
+
+/// Sets what happens to the stored stencil value when the stencil test fails ('stencil_fail'),
+/// the stencil test passes but the depth test fails ('depth_fail') and both pass ('depth_pass') - 'glStencilOp'
+pub fn set_stencil_op(stencil_fail: StencilOp, depth_fail: StencilOp, depth_pass: StencilOp) {
+    unsafe{ gl_function(|| gl::StencilOp(stencil_fail as GLenum, depth_fail as GLenum, depth_pass as GLenum)) };
+}
+
+/// Sets which bits of the stencil buffer can be written - 'glStencilMask'
+pub fn set_stencil_mask(mask: u32) {
+    unsafe{ gl_function(|| gl::StencilMask(mask)) };
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// The set of memory accesses a 'glMemoryBarrier' should order, combined with '|'
+///
+/// Each flag describes how data written by shaders is going to be read afterwards,
+/// e.g. 'VERTEX_ATTRIB_ARRAY' when a compute shader writes vertex data
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MemoryBarrier(GLbitfield);
+
+impl MemoryBarrier {
+    pub const VERTEX_ATTRIB_ARRAY: Self = Self(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+    pub const ELEMENT_ARRAY: Self = Self(gl::ELEMENT_ARRAY_BARRIER_BIT);
+    pub const UNIFORM: Self = Self(gl::UNIFORM_BARRIER_BIT);
+    pub const TEXTURE_FETCH: Self = Self(gl::TEXTURE_FETCH_BARRIER_BIT);
+    pub const SHADER_IMAGE_ACCESS: Self = Self(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+    pub const COMMAND: Self = Self(gl::COMMAND_BARRIER_BIT);
+    pub const PIXEL_BUFFER: Self = Self(gl::PIXEL_BUFFER_BARRIER_BIT);
+    pub const TEXTURE_UPDATE: Self = Self(gl::TEXTURE_UPDATE_BARRIER_BIT);
+    pub const BUFFER_UPDATE: Self = Self(gl::BUFFER_UPDATE_BARRIER_BIT);
+    pub const FRAMEBUFFER: Self = Self(gl::FRAMEBUFFER_BARRIER_BIT);
+    pub const TRANSFORM_FEEDBACK: Self = Self(gl::TRANSFORM_FEEDBACK_BARRIER_BIT);
+    pub const ATOMIC_COUNTER: Self = Self(gl::ATOMIC_COUNTER_BARRIER_BIT);
+    pub const SHADER_STORAGE: Self = Self(gl::SHADER_STORAGE_BARRIER_BIT);
+    pub const ALL: Self = Self(gl::ALL_BARRIER_BITS);
+
+    #[must_use] pub const fn bits(self) -> GLbitfield { self.0 }
+}
+
+impl std::ops::BitOr for MemoryBarrier {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self { Self(self.0 | rhs.0) }
+}
+
+impl std::ops::BitOrAssign for MemoryBarrier {
+    fn bitor_assign(&mut self, rhs: Self) { self.0 |= rhs.0; }
+}
+
+/// Orders shader memory writes before subsequent reads of the given kinds - 'glMemoryBarrier'
+pub fn memory_barrier(barriers: MemoryBarrier) {
+    unsafe{ gl_function(|| gl::MemoryBarrier(barriers.bits())) };
+}
\ No newline at end of file
@@ -0,0 +1,52 @@
+use crate::validation::gl_function;
+
+use std::time::Duration;
+
+use gl::types::*;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WaitResult {
+    /// The fence was already signaled when the wait began
+    AlreadySignaled,
+    /// The fence was signaled before the timeout expired
+    Signaled,
+    TimeoutExpired,
+    Failed,
+}
+
+/// A fence which is signaled once the GPU has completed all commands issued before it
+///
+/// Useful for waiting on readbacks (e.g. storage buffers or screenshots) without a full 'glFinish'
+pub struct Fence(GLsync);
+
+impl Fence {
+    /// Inserts a fence after all previously issued commands - 'glFenceSync'
+    pub fn insert() -> Self {
+        let mut sync = std::ptr::null();
+        unsafe{ gl_function(|| sync = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0)) };
+        Self(sync)
+    }
+
+    /// Blocks until the fence is signaled or the timeout expires - 'glClientWaitSync'
+    ///
+    /// Pending commands are flushed, so the fence is guaranteed to eventually signal
+    pub fn wait(&self, timeout: Duration) -> WaitResult {
+        let timeout = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+
+        let mut result = gl::WAIT_FAILED;
+        unsafe{ gl_function(|| result = gl::ClientWaitSync(self.0, gl::SYNC_FLUSH_COMMANDS_BIT, timeout)) };
+
+        match result {
+            gl::ALREADY_SIGNALED => WaitResult::AlreadySignaled,
+            gl::CONDITION_SATISFIED => WaitResult::Signaled,
+            gl::TIMEOUT_EXPIRED => WaitResult::TimeoutExpired,
+            _ => WaitResult::Failed,
+        }
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        unsafe{ gl_function(|| gl::DeleteSync(self.0)) };
+    }
+}
\ No newline at end of file
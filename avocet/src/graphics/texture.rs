@@ -0,0 +1,600 @@
+use crate::{
+    graphics::{PackedRows, RawHandle, ResourceHandle, VertexResource, VertexResourceLifecycle},
+    validation::{self, gl_function},
+    version,
+};
+
+use std::path::Path;
+
+use gl::types::*;
+
+pub struct TextureLifecycle;
+
+/// A texture's type is fixed by the target it's first bound to, so it can't be created
+/// ahead of time - label textures after creation, e.g. with 'Texture2D::set_label'
+impl VertexResourceLifecycle for TextureLifecycle {
+    const IDENTIFIER: GLenum = gl::TEXTURE;
+
+    fn generate<const N: usize>() -> [ResourceHandle; N] {
+        let mut result = [const { ResourceHandle(0) }; N];
+        unsafe{ gl_function(|| gl::GenTextures(N as _, result.as_mut_ptr() as _)) };
+        result
+    }
+
+    fn destroy(handles: &[ResourceHandle]) {
+        unsafe{ gl_function(|| gl::DeleteTextures(handles.len() as _, handles.as_ptr() as _)) };
+    }
+}
+
+pub type TextureResource = VertexResource<1, TextureLifecycle>;
+
+/// Why a texture couldn't be loaded from a file
+#[derive(Debug)]
+pub enum TextureError {
+    /// The file couldn't be read
+    Io(std::io::Error),
+    /// The file is corrupt or truncated
+    Decode(String),
+    /// The file isn't in one of the supported formats, see 'decode_image'
+    UnsupportedFormat,
+}
+
+impl std::fmt::Display for TextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextureError::Io(error) => write!(f, "{}", error),
+            TextureError::Decode(message) => write!(f, "Unable to decode image: {}", message),
+            TextureError::UnsupportedFormat => write!(f, "Only PNG, JPEG, TGA and binary PGM/PPM images are supported."),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextureError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TextureError {
+    fn from(error: std::io::Error) -> Self { TextureError::Io(error) }
+}
+
+/// Images are decoded from bytes already in memory, so an I/O error means the data ended early
+impl From<image::ImageError> for TextureError {
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(_) => TextureError::Decode("Image data is truncated.".to_owned()),
+            image::ImageError::Unsupported(_) => TextureError::UnsupportedFormat,
+            error => TextureError::Decode(error.to_string()),
+        }
+    }
+}
+
+/// The channels of 8-bit pixel data, e.g. 'R8' for heightmaps and masks
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    R8,
+    RG8,
+    RGB8,
+    #[default]
+    RGBA8,
+}
+
+impl TextureFormat {
+    #[must_use]
+    pub const fn channels(self) -> u32 {
+        match self {
+            TextureFormat::R8 => 1,
+            TextureFormat::RG8 => 2,
+            TextureFormat::RGB8 => 3,
+            TextureFormat::RGBA8 => 4,
+        }
+    }
+
+    /// There are no core sRGB formats with fewer than three channels, single and two channel
+    /// data is generally not a colour anyway (e.g. masks) so is always stored linearly
+    const fn internal_format(self, srgb: bool) -> GLint {
+        (match (self, srgb) {
+            (TextureFormat::R8, _) => gl::R8,
+            (TextureFormat::RG8, _) => gl::RG8,
+            (TextureFormat::RGB8, false) => gl::RGB8,
+            (TextureFormat::RGB8, true) => gl::SRGB8,
+            (TextureFormat::RGBA8, false) => gl::RGBA8,
+            (TextureFormat::RGBA8, true) => gl::SRGB8_ALPHA8,
+        }) as GLint
+    }
+
+    /// The layout of the uploaded data, for 'glTexImage*'
+    const fn data_format(self) -> GLenum {
+        match self {
+            TextureFormat::R8 => gl::RED,
+            TextureFormat::RG8 => gl::RG,
+            TextureFormat::RGB8 => gl::RGB,
+            TextureFormat::RGBA8 => gl::RGBA,
+        }
+    }
+}
+
+/// How texels are filtered when sampled - 'GL_TEXTURE_MIN_FILTER' and 'GL_TEXTURE_MAG_FILTER'
+///
+/// The mipmap filters only apply to minification, and require the texture to have mipmaps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum TextureFilter {
+    Nearest = gl::NEAREST,
+    Linear = gl::LINEAR,
+    NearestMipmapNearest = gl::NEAREST_MIPMAP_NEAREST,
+    LinearMipmapNearest = gl::LINEAR_MIPMAP_NEAREST,
+    NearestMipmapLinear = gl::NEAREST_MIPMAP_LINEAR,
+    LinearMipmapLinear = gl::LINEAR_MIPMAP_LINEAR,
+}
+
+impl TextureFilter {
+    #[must_use]
+    pub const fn uses_mipmaps(self) -> bool {
+        !matches!(self, TextureFilter::Nearest | TextureFilter::Linear)
+    }
+}
+
+/// How texture coordinates outside 0.0 to 1.0 are handled - 'GL_TEXTURE_WRAP_*'
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum TextureWrap {
+    Repeat = gl::REPEAT,
+    MirroredRepeat = gl::MIRRORED_REPEAT,
+    ClampToEdge = gl::CLAMP_TO_EDGE,
+    ClampToBorder = gl::CLAMP_TO_BORDER,
+}
+
+/// Panics under validation if 'mag' is a mipmap filter, which is only valid for minification
+pub(super) fn check_mag_filter(mag: TextureFilter) {
+    if const { validation::should_validate() } && mag.uses_mipmaps() {
+        log::error!("{:?} is only valid as a minification filter", mag);
+        panic!("Invalid magnification filter");
+    }
+}
+
+fn internal_format(srgb: bool) -> GLint {
+    TextureFormat::RGBA8.internal_format(srgb)
+}
+
+fn bind_to_unit(target: GLenum, resource: &TextureResource, unit: u32) {
+    unsafe{
+        gl_function(|| gl::ActiveTexture(gl::TEXTURE0 + unit));
+        gl_function(|| gl::BindTexture(target, resource.handle().index()));
+    }
+}
+
+/// Sets a texture's own filters, leaving it bound to unit 0
+fn set_filters(target: GLenum, resource: &TextureResource, min: TextureFilter, mag: TextureFilter) {
+    check_mag_filter(mag);
+
+    bind_to_unit(target, resource, 0);
+    unsafe{
+        gl_function(|| gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, min as GLint));
+        gl_function(|| gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, mag as GLint));
+    }
+}
+
+/// Sets how each of a texture's coordinates wrap - including 'R' for 3D textures - leaving
+/// it bound to unit 0
+fn set_wrapping(target: GLenum, resource: &TextureResource, wrap: TextureWrap) {
+    bind_to_unit(target, resource, 0);
+    unsafe{
+        gl_function(|| gl::TexParameteri(target, gl::TEXTURE_WRAP_S, wrap as GLint));
+        gl_function(|| gl::TexParameteri(target, gl::TEXTURE_WRAP_T, wrap as GLint));
+        if target == gl::TEXTURE_3D {
+            gl_function(|| gl::TexParameteri(target, gl::TEXTURE_WRAP_R, wrap as GLint));
+        }
+    }
+}
+
+/// The length of tightly packed 8-bit data with 'channels' per texel - 'None' if it overflows
+///
+/// The product of 32-bit dimensions easily exceeds 'u32', and a wrapped length would let a short
+/// slice pass the size check, so GL reads past its end
+pub(crate) fn packed_length(channels: u32, dimensions: &[u32]) -> Option<usize> {
+    dimensions.iter().try_fold(channels as usize, |length, &dimension| length.checked_mul(dimension as usize))
+}
+
+fn set_default_filters(target: GLenum) {
+    // The default minification filter requires mipmaps, without which the texture is incomplete
+    unsafe{
+        gl_function(|| gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint));
+        gl_function(|| gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint));
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+
+pub struct Texture2D {
+    resource: TextureResource,
+    width: u32,
+    height: u32,
+    format: TextureFormat,
+}
+
+impl Texture2D {
+    /// Creates a texture from tightly packed 8-bit RGBA pixel data
+    ///
+    /// Colour data authored in an image editor is almost always sRGB (gamma) encoded,
+    /// in which case 'srgb' should be true: the texture is stored as 'GL_SRGB8_ALPHA8'
+    /// and the GPU converts it to linear values when sampled, so lighting maths is correct.
+    /// Data which isn't a colour (normal maps, masks, etc) is already linear and should use false.
+    pub fn new(width: u32, height: u32, data: &[u8], srgb: bool) -> Self {
+        Texture2D::with_format(width, height, data, TextureFormat::RGBA8, srgb)
+    }
+
+    /// Creates a texture from tightly packed 8-bit pixel data with the given channels
+    ///
+    /// 'srgb' only applies to 'RGB8' and 'RGBA8', see 'new'
+    pub fn with_format(width: u32, height: u32, data: &[u8], format: TextureFormat, srgb: bool) -> Self {
+        assert_eq!(Some(data.len()), packed_length(format.channels(), &[width, height]), "Texture data must be tightly packed {:?}", format);
+
+        let texture = Self { resource: TextureResource::new(), width, height, format };
+        texture.bind(0);
+
+        let _packed_rows = PackedRows::for_upload(width as usize * format.channels() as usize);
+        unsafe{
+            gl_function(|| gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format.internal_format(srgb),
+                width as _,
+                height as _,
+                0,
+                format.data_format(),
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as _
+            ));
+        }
+        set_default_filters(gl::TEXTURE_2D);
+
+        texture
+    }
+
+    /// Wraps a texture created by other code without taking ownership: dropping the
+    /// 'Texture2D' doesn't delete it. The size and format are only recorded, not checked
+    ///
+    /// # Safety
+    /// 'handle' must name a 2D texture which its owner keeps alive for as long as the
+    /// 'Texture2D' is used - see 'VertexResource::from_raw_unowned'
+    pub unsafe fn from_raw_unowned(handle: GLuint, width: u32, height: u32, format: TextureFormat) -> Self {
+        Self { resource: TextureResource::from_raw_unowned(handle), width, height, format }
+    }
+
+    /// Loads an image, choosing the format matching its channels (see 'decode_image') - see
+    /// 'new' for the meaning of 'srgb', which greyscale textures ignore
+    ///
+    /// Rows are uploaded in file order (top first), so the image's top is at 'v = 0'
+    pub fn from_file<P: AsRef<Path>>(path: P, srgb: bool) -> Result<Self, TextureError> {
+        let bytes = std::fs::read(path.as_ref()).map_err(|error| {
+            std::io::Error::new(error.kind(), format!("Unable to read texture {:?}: {}", path.as_ref(), error))
+        })?;
+        let image = decode_image(&bytes)?;
+
+        Ok(Texture2D::with_format(image.width, image.height, &image.data, image.format, srgb))
+    }
+
+    /// Binds this texture to the given texture unit
+    pub fn bind(&self, unit: u32) {
+        bind_to_unit(gl::TEXTURE_2D, &self.resource, unit);
+    }
+
+    /// Sets the texture's own filters, which a 'Sampler' bound to the same unit overrides
+    ///
+    /// Leaves this texture bound to unit 0
+    pub fn set_filter(&self, min: TextureFilter, mag: TextureFilter) {
+        set_filters(gl::TEXTURE_2D, &self.resource, min, mag);
+    }
+
+    /// Sets how both texture coordinates wrap, which a 'Sampler' bound to the same unit overrides
+    ///
+    /// Leaves this texture bound to unit 0
+    pub fn set_wrap(&self, wrap: TextureWrap) {
+        set_wrapping(gl::TEXTURE_2D, &self.resource, wrap);
+    }
+
+    #[must_use] pub fn width(&self) -> u32 { self.width }
+    #[must_use] pub fn height(&self) -> u32 { self.height }
+    #[must_use] pub fn format(&self) -> TextureFormat { self.format }
+
+    /// Names the texture in graphics debuggers - see 'VertexResource::set_label'
+    pub fn set_label(&self, label: &str) { self.resource.set_label(label); }
+
+    #[must_use] pub fn resource(&self) -> &TextureResource { &self.resource }
+
+    /// The texture's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+}
+
+/// An image decoded by 'decode_image', converted to 8 bits per channel
+#[derive(Debug)]
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    /// Matches the file's channels, see 'decode_image'
+    pub format: TextureFormat,
+    /// Tightly packed rows, top first
+    pub data: Vec<u8>,
+}
+
+/// Decodes a PNG, JPEG, TGA or binary PGM/PPM image, detecting its channels: greyscale
+/// ('L8') becomes 'R8', greyscale with alpha ('LA8') 'RG8', and colour 'RGB8' or 'RGBA8'
+///
+/// Higher bit depths are reduced to 8 bits per channel
+pub fn decode_image(bytes: &[u8]) -> Result<DecodedImage, TextureError> {
+    let image = image::load_from_memory(bytes)?;
+    let (width, height) = (image.width(), image.height());
+
+    let color = image.color();
+    let (format, data) = match (color.has_color(), color.has_alpha()) {
+        (false, false) => (TextureFormat::R8, image.into_luma8().into_raw()),
+        (false, true) => (TextureFormat::RG8, image.into_luma_alpha8().into_raw()),
+        (true, false) => (TextureFormat::RGB8, image.into_rgb8().into_raw()),
+        (true, true) => (TextureFormat::RGBA8, image.into_rgba8().into_raw()),
+    };
+
+    Ok(DecodedImage { width, height, format, data })
+}
+
+/// Binds each texture to its unit, e.g. a material's albedo, normal and roughness maps
+///
+/// When the units are consecutive and OpenGL 4.4 is available this is a single 'glBindTextures',
+/// otherwise each texture is bound in turn. Either way, other units are left untouched.
+pub fn bind_textures(bindings: &[(&Texture2D, u32)]) {
+    if const { validation::should_validate() } {
+        let max_units = version::get_opengl_limits().max_combined_texture_image_units;
+        if let Some((_, unit)) = bindings.iter().find(|(_, unit)| *unit as usize >= max_units) {
+            log::error!("Texture unit {} exceeds the limit of {} units", unit, max_units);
+            panic!("Texture unit {} exceeds the limit of {} units", unit, max_units);
+        }
+    }
+
+    let Some(&(_, first)) = bindings.first() else {
+        return;
+    };
+
+    let consecutive = bindings.iter().enumerate().all(|(index, (_, unit))| *unit == first + index as u32);
+    if consecutive && version::get_opengl_version().at_least(4, 4) {
+        let textures: Vec<GLuint> = bindings.iter().map(|(texture, _)| texture.resource.handle().index()).collect();
+        unsafe{ gl_function(|| gl::BindTextures(first, textures.len() as _, textures.as_ptr())) };
+    } else {
+        for (texture, unit) in bindings {
+            texture.bind(*unit);
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// An array of 2D textures of the same size, sampled with 'sampler2DArray' - e.g. shadow
+/// cascades or a terrain atlas without bleeding between tiles
+///
+/// Layers are addressed by the third texture coordinate, which isn't normalised
+pub struct Texture2DArray {
+    resource: TextureResource,
+    width: u32,
+    height: u32,
+    layers: u32,
+}
+
+impl Texture2DArray {
+    /// Allocates storage for 'layers' RGBA8 layers, whose contents are undefined until
+    /// uploaded with 'upload_layer' - see 'Texture2D::new' for the meaning of 'srgb'
+    pub fn new(width: u32, height: u32, layers: u32, srgb: bool) -> Self {
+        let texture = Self { resource: TextureResource::new(), width, height, layers };
+        texture.bind(0);
+
+        unsafe{
+            gl_function(|| gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                internal_format(srgb),
+                width as _,
+                height as _,
+                layers as _,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null()
+            ));
+        }
+        set_default_filters(gl::TEXTURE_2D_ARRAY);
+
+        texture
+    }
+
+    /// Replaces the contents of a single layer with tightly packed 8-bit RGBA pixel data - 'glTexSubImage3D'
+    pub fn upload_layer(&self, layer: u32, data: &[u8]) {
+        assert!(layer < self.layers, "Layer {} is out of range ({} layers)", layer, self.layers);
+        assert_eq!(Some(data.len()), packed_length(4, &[self.width, self.height]), "Texture data must be tightly packed RGBA8");
+
+        self.bind(0);
+        let _packed_rows = PackedRows::for_upload(self.width as usize * 4);
+        unsafe{
+            gl_function(|| gl::TexSubImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                0,
+                0,
+                layer as _,
+                self.width as _,
+                self.height as _,
+                1,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as _
+            ));
+        }
+    }
+
+    /// Binds this texture to the given texture unit
+    pub fn bind(&self, unit: u32) {
+        bind_to_unit(gl::TEXTURE_2D_ARRAY, &self.resource, unit);
+    }
+
+    /// Sets the texture's own filters - see 'Texture2D::set_filter'
+    pub fn set_filter(&self, min: TextureFilter, mag: TextureFilter) {
+        set_filters(gl::TEXTURE_2D_ARRAY, &self.resource, min, mag);
+    }
+
+    /// Sets how the 'S' and 'T' coordinates wrap, the layer coordinate is never wrapped -
+    /// see 'Texture2D::set_wrap'
+    pub fn set_wrap(&self, wrap: TextureWrap) {
+        set_wrapping(gl::TEXTURE_2D_ARRAY, &self.resource, wrap);
+    }
+
+    #[must_use] pub fn width(&self) -> u32 { self.width }
+    #[must_use] pub fn height(&self) -> u32 { self.height }
+    #[must_use] pub fn layers(&self) -> u32 { self.layers }
+
+    /// Names the texture in graphics debuggers - see 'VertexResource::set_label'
+    pub fn set_label(&self, label: &str) { self.resource.set_label(label); }
+
+    #[must_use] pub fn resource(&self) -> &TextureResource { &self.resource }
+
+    /// The texture's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// A volume texture, sampled with 'sampler3D' and filtered across all three dimensions
+pub struct Texture3D {
+    resource: TextureResource,
+    width: u32,
+    height: u32,
+    depth: u32,
+}
+
+impl Texture3D {
+    /// Creates a texture from tightly packed 8-bit RGBA pixel data, ordered by slice then row
+    ///
+    /// See 'Texture2D::new' for the meaning of 'srgb'
+    pub fn new(width: u32, height: u32, depth: u32, data: &[u8], srgb: bool) -> Self {
+        assert_eq!(Some(data.len()), packed_length(4, &[width, height, depth]), "Texture data must be tightly packed RGBA8");
+
+        let texture = Self { resource: TextureResource::new(), width, height, depth };
+        texture.bind(0);
+
+        let _packed_rows = PackedRows::for_upload(width as usize * 4);
+        unsafe{
+            gl_function(|| gl::TexImage3D(
+                gl::TEXTURE_3D,
+                0,
+                internal_format(srgb),
+                width as _,
+                height as _,
+                depth as _,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr() as _
+            ));
+        }
+        set_default_filters(gl::TEXTURE_3D);
+
+        texture
+    }
+
+    /// Binds this texture to the given texture unit
+    pub fn bind(&self, unit: u32) {
+        bind_to_unit(gl::TEXTURE_3D, &self.resource, unit);
+    }
+
+    /// Sets the texture's own filters - see 'Texture2D::set_filter'
+    pub fn set_filter(&self, min: TextureFilter, mag: TextureFilter) {
+        set_filters(gl::TEXTURE_3D, &self.resource, min, mag);
+    }
+
+    /// Sets how all three texture coordinates wrap - see 'Texture2D::set_wrap'
+    pub fn set_wrap(&self, wrap: TextureWrap) {
+        set_wrapping(gl::TEXTURE_3D, &self.resource, wrap);
+    }
+
+    #[must_use] pub fn width(&self) -> u32 { self.width }
+    #[must_use] pub fn height(&self) -> u32 { self.height }
+    #[must_use] pub fn depth(&self) -> u32 { self.depth }
+
+    /// Names the texture in graphics debuggers - see 'VertexResource::set_label'
+    pub fn set_label(&self, label: &str) { self.resource.set_label(label); }
+
+    #[must_use] pub fn resource(&self) -> &TextureResource { &self.resource }
+
+    /// The texture's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// A colour target with several samples per texel for offscreen MSAA, sampled (if at all)
+/// with 'sampler2DMS' and 'texelFetch'
+///
+/// It can't be filtered or uploaded to: render to it through 'Framebuffer::attach_color_multisample'
+/// and resolve it to a regular texture or the window with 'Framebuffer::blit_to'
+pub struct Texture2DMultisample {
+    resource: TextureResource,
+    width: u32,
+    height: u32,
+    samples: u32,
+}
+
+impl Texture2DMultisample {
+    /// Allocates RGBA8 storage with 'samples' samples per texel - 'glTexImage2DMultisample'
+    ///
+    /// See 'Texture2D::new' for the meaning of 'srgb'. Every texel has the same sample
+    /// locations, so it can share a framebuffer with a multisampled 'Renderbuffer'.
+    pub fn new(width: u32, height: u32, samples: u32, srgb: bool) -> std::io::Result<Self> {
+        check_sample_count(samples)?;
+
+        let texture = Self { resource: TextureResource::new(), width, height, samples };
+        texture.bind(0);
+
+        unsafe{
+            gl_function(|| gl::TexImage2DMultisample(
+                gl::TEXTURE_2D_MULTISAMPLE,
+                samples as _,
+                internal_format(srgb) as GLenum,
+                width as _,
+                height as _,
+                gl::TRUE
+            ));
+        }
+
+        Ok(texture)
+    }
+
+    /// Binds this texture to the given texture unit
+    pub fn bind(&self, unit: u32) {
+        bind_to_unit(gl::TEXTURE_2D_MULTISAMPLE, &self.resource, unit);
+    }
+
+    #[must_use] pub fn width(&self) -> u32 { self.width }
+    #[must_use] pub fn height(&self) -> u32 { self.height }
+    #[must_use] pub fn samples(&self) -> u32 { self.samples }
+
+    /// Names the texture in graphics debuggers - see 'VertexResource::set_label'
+    pub fn set_label(&self, label: &str) { self.resource.set_label(label); }
+
+    #[must_use] pub fn resource(&self) -> &TextureResource { &self.resource }
+
+    /// The texture's name - see 'ShaderProgram::raw_handle' for who may delete it
+    #[must_use] pub fn raw_handle(&self) -> RawHandle { self.resource.handle().raw() }
+}
+
+/// Multisampled storage needs at least one sample and at most 'GL_MAX_SAMPLES'
+pub(super) fn check_sample_count(samples: u32) -> std::io::Result<()> {
+    let max_samples = version::get_opengl_limits().max_samples;
+    if samples == 0 || samples as usize > max_samples {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} samples is outside the supported range of 1 to {}.", samples, max_samples)
+        ));
+    }
+
+    Ok(())
+}
\ No newline at end of file
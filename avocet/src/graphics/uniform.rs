@@ -0,0 +1,452 @@
+use crate::{
+    graphics::{current_program, ShaderProgram, Texture2D},
+    validation::{self, gl_function},
+};
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::CString,
+};
+
+use gl::types::*;
+
+/// The reflection data of an active uniform - 'glGetActiveUniform'
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct UniformInfo {
+    location: GLint,
+    gl_type: GLenum,
+    /// The number of array elements from this location onwards, 1 for non-arrays
+    size: GLint,
+}
+
+/// Lazily populated map of uniform names to their reflection data
+#[derive(Debug, Default)]
+pub(super) struct UniformCache(RefCell<Option<HashMap<String, UniformInfo>>>);
+
+/// A uniform resolved once with 'ShaderProgram::uniform_location', for the 'set_*_at' setters
+///
+/// Skips the name lookup of the named setters, for uniforms updated every frame. Only valid
+/// for the program it came from, which validation checks. Inactive uniforms get a location
+/// which GL ignores, so setting one is silently skipped as with the named setters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformLocation {
+    location: GLint,
+    gl_type: GLenum,
+    program: GLuint,
+}
+
+impl UniformLocation {
+    /// False if the uniform isn't active in the program, e.g. the compiler optimised it away
+    #[must_use] pub fn is_active(self) -> bool { self.location >= 0 }
+}
+
+// ------------------------------------------------------------------------------------------
+
+/// Integer setters are also used for booleans and samplers (the texture unit)
+const INTEGER_TYPES: &[GLenum] = &[
+    gl::INT,
+    gl::BOOL,
+    gl::SAMPLER_1D,
+    gl::SAMPLER_2D,
+    gl::SAMPLER_3D,
+    gl::SAMPLER_CUBE,
+    gl::SAMPLER_1D_SHADOW,
+    gl::SAMPLER_2D_SHADOW,
+    gl::SAMPLER_1D_ARRAY,
+    gl::SAMPLER_2D_ARRAY,
+    gl::SAMPLER_1D_ARRAY_SHADOW,
+    gl::SAMPLER_2D_ARRAY_SHADOW,
+    gl::SAMPLER_2D_MULTISAMPLE,
+    gl::SAMPLER_2D_MULTISAMPLE_ARRAY,
+    gl::SAMPLER_CUBE_SHADOW,
+    gl::SAMPLER_BUFFER,
+    gl::SAMPLER_2D_RECT,
+    gl::INT_SAMPLER_2D,
+    gl::INT_SAMPLER_3D,
+    gl::INT_SAMPLER_CUBE,
+    gl::INT_SAMPLER_2D_ARRAY,
+    gl::UNSIGNED_INT_SAMPLER_2D,
+    gl::UNSIGNED_INT_SAMPLER_3D,
+    gl::UNSIGNED_INT_SAMPLER_CUBE,
+    gl::UNSIGNED_INT_SAMPLER_2D_ARRAY,
+];
+
+/// The sampler types a 'Texture2D' can be bound to
+const SAMPLER_2D_TYPES: &[GLenum] = &[
+    gl::SAMPLER_2D,
+    gl::SAMPLER_2D_SHADOW,
+    gl::INT_SAMPLER_2D,
+    gl::UNSIGNED_INT_SAMPLER_2D,
+];
+
+// Setters apply to the current program, so the program must be bound first - which validation
+// checks. Binding implicitly would silently change the program used by subsequent draws
+
+impl ShaderProgram {
+    pub fn set_uniform_f32(&self, name: &str, value: f32) {
+        if let Some(location) = self.checked_location(name, "set_uniform_f32", &[gl::FLOAT]) {
+            self.check_bound("set_uniform_f32");
+            unsafe{ gl_function(|| gl::Uniform1f(location, value)) };
+        }
+    }
+
+    /// Also sets 'bool' uniforms and samplers, where the value is the texture unit
+    pub fn set_uniform_i32(&self, name: &str, value: i32) {
+        if let Some(location) = self.checked_location(name, "set_uniform_i32", INTEGER_TYPES) {
+            self.check_bound("set_uniform_i32");
+            unsafe{ gl_function(|| gl::Uniform1i(location, value)) };
+        }
+    }
+
+    pub fn set_uniform_u32(&self, name: &str, value: u32) {
+        if let Some(location) = self.checked_location(name, "set_uniform_u32", &[gl::UNSIGNED_INT, gl::BOOL]) {
+            self.check_bound("set_uniform_u32");
+            unsafe{ gl_function(|| gl::Uniform1ui(location, value)) };
+        }
+    }
+
+    pub fn set_uniform_vec2(&self, name: &str, value: [f32; 2]) {
+        if let Some(location) = self.checked_location(name, "set_uniform_vec2", &[gl::FLOAT_VEC2]) {
+            self.check_bound("set_uniform_vec2");
+            unsafe{ gl_function(|| gl::Uniform2f(location, value[0], value[1])) };
+        }
+    }
+
+    pub fn set_uniform_vec3(&self, name: &str, value: [f32; 3]) {
+        if let Some(location) = self.checked_location(name, "set_uniform_vec3", &[gl::FLOAT_VEC3]) {
+            self.check_bound("set_uniform_vec3");
+            unsafe{ gl_function(|| gl::Uniform3f(location, value[0], value[1], value[2])) };
+        }
+    }
+
+    pub fn set_uniform_vec4(&self, name: &str, value: [f32; 4]) {
+        if let Some(location) = self.checked_location(name, "set_uniform_vec4", &[gl::FLOAT_VEC4]) {
+            self.check_bound("set_uniform_vec4");
+            unsafe{ gl_function(|| gl::Uniform4f(location, value[0], value[1], value[2], value[3])) };
+        }
+    }
+
+    /// The matrix is given as four columns, matching GLSL's column-major layout
+    pub fn set_uniform_mat4(&self, name: &str, value: &[[f32; 4]; 4]) {
+        if let Some(location) = self.checked_location(name, "set_uniform_mat4", &[gl::FLOAT_MAT4]) {
+            self.check_bound("set_uniform_mat4");
+            unsafe{ gl_function(|| gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr() as _)) };
+        }
+    }
+
+    /// Binds 'texture' to the texture unit and points the named 'sampler2D' uniform at the
+    /// same unit, so the two can't get out of sync
+    pub fn set_sampler(&self, name: &str, texture: &Texture2D, unit: u32) {
+        texture.bind(unit);
+
+        if let Some(location) = self.checked_location(name, "set_sampler", SAMPLER_2D_TYPES) {
+            self.check_bound("set_sampler");
+            unsafe{ gl_function(|| gl::Uniform1i(location, unit as _)) };
+        }
+    }
+
+    // --------------------------------------------------------------------------------------
+    // Setting through a pre-resolved 'UniformLocation', see 'uniform_location'
+
+    /// Resolves the named uniform once, for use with the 'set_*_at' setters
+    pub fn uniform_location(&self, name: &str) -> UniformLocation {
+        let (location, gl_type) = self.uniform_info(name).map_or((-1, 0), |info| (info.location, info.gl_type));
+        UniformLocation { location, gl_type, program: self.index() }
+    }
+
+    pub fn set_f32_at(&self, location: UniformLocation, value: f32) {
+        let location = self.checked_location_at(location, "set_f32_at", &[gl::FLOAT]);
+        self.check_bound("set_f32_at");
+        unsafe{ gl_function(|| gl::Uniform1f(location, value)) };
+    }
+
+    /// Also sets 'bool' uniforms and samplers, where the value is the texture unit
+    pub fn set_i32_at(&self, location: UniformLocation, value: i32) {
+        let location = self.checked_location_at(location, "set_i32_at", INTEGER_TYPES);
+        self.check_bound("set_i32_at");
+        unsafe{ gl_function(|| gl::Uniform1i(location, value)) };
+    }
+
+    pub fn set_u32_at(&self, location: UniformLocation, value: u32) {
+        let location = self.checked_location_at(location, "set_u32_at", &[gl::UNSIGNED_INT, gl::BOOL]);
+        self.check_bound("set_u32_at");
+        unsafe{ gl_function(|| gl::Uniform1ui(location, value)) };
+    }
+
+    pub fn set_vec2_at(&self, location: UniformLocation, value: [f32; 2]) {
+        let location = self.checked_location_at(location, "set_vec2_at", &[gl::FLOAT_VEC2]);
+        self.check_bound("set_vec2_at");
+        unsafe{ gl_function(|| gl::Uniform2f(location, value[0], value[1])) };
+    }
+
+    pub fn set_vec3_at(&self, location: UniformLocation, value: [f32; 3]) {
+        let location = self.checked_location_at(location, "set_vec3_at", &[gl::FLOAT_VEC3]);
+        self.check_bound("set_vec3_at");
+        unsafe{ gl_function(|| gl::Uniform3f(location, value[0], value[1], value[2])) };
+    }
+
+    pub fn set_vec4_at(&self, location: UniformLocation, value: [f32; 4]) {
+        let location = self.checked_location_at(location, "set_vec4_at", &[gl::FLOAT_VEC4]);
+        self.check_bound("set_vec4_at");
+        unsafe{ gl_function(|| gl::Uniform4f(location, value[0], value[1], value[2], value[3])) };
+    }
+
+    /// The matrix is given as four columns, see 'set_uniform_mat4'
+    pub fn set_mat4_at(&self, location: UniformLocation, value: &[[f32; 4]; 4]) {
+        let location = self.checked_location_at(location, "set_mat4_at", &[gl::FLOAT_MAT4]);
+        self.check_bound("set_mat4_at");
+        unsafe{ gl_function(|| gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr() as _)) };
+    }
+
+    /// Sets consecutive elements of a 'float' array, starting from the named element
+    ///
+    /// See 'set_uniform_vec3_array' for how array names are resolved
+    pub fn set_uniform_f32_array(&self, name: &str, values: &[f32]) {
+        if let Some(location) = self.checked_array_location(name, "set_uniform_f32_array", &[gl::FLOAT], values.len()) {
+            self.check_bound("set_uniform_f32_array");
+            unsafe{ gl_function(|| gl::Uniform1fv(location, values.len() as _, values.as_ptr())) };
+        }
+    }
+
+    /// Sets consecutive elements of a 'vec3' array, e.g. 'uniform vec3 lights[8]' - 'glUniform3fv'
+    ///
+    /// Array elements occupy sequential locations, so uploading from 'lights' (or 'lights[0]')
+    /// sets elements 0 onwards, whereas an indexed name such as 'lights[2]' starts from element 2.
+    /// Indexed names are resolved by 'glGetUniformLocation' and cached.
+    ///
+    /// Arrays of structs have no single location, instead each member of each element is
+    /// its own uniform: set them individually, e.g. 'set_uniform_vec3("lights[2].position", ...)'.
+    pub fn set_uniform_vec3_array(&self, name: &str, values: &[[f32; 3]]) {
+        if let Some(location) = self.checked_array_location(name, "set_uniform_vec3_array", &[gl::FLOAT_VEC3], values.len()) {
+            self.check_bound("set_uniform_vec3_array");
+            unsafe{ gl_function(|| gl::Uniform3fv(location, values.len() as _, values.as_ptr() as _)) };
+        }
+    }
+
+    /// Sets consecutive elements of a 'vec4' array - see 'set_uniform_vec3_array'
+    pub fn set_uniform_vec4_array(&self, name: &str, values: &[[f32; 4]]) {
+        if let Some(location) = self.checked_array_location(name, "set_uniform_vec4_array", &[gl::FLOAT_VEC4], values.len()) {
+            self.check_bound("set_uniform_vec4_array");
+            unsafe{ gl_function(|| gl::Uniform4fv(location, values.len() as _, values.as_ptr() as _)) };
+        }
+    }
+
+    // --------------------------------------------------------------------------------------
+    // Reading back the current values - 'glGetUniform*', which doesn't need the program bound.
+    // 'None' if the uniform isn't active, as with the setters
+
+    pub fn get_uniform_f32(&self, name: &str) -> Option<f32> {
+        self.get_uniform_floats::<1>(name, "get_uniform_f32", &[gl::FLOAT]).map(|[value]| value)
+    }
+
+    pub fn get_uniform_i32(&self, name: &str) -> Option<i32> {
+        let location = self.checked_location(name, "get_uniform_i32", INTEGER_TYPES)?;
+        let mut value = 0;
+        unsafe{ gl_function(|| gl::GetUniformiv(self.index(), location, &mut value)) };
+        Some(value)
+    }
+
+    pub fn get_uniform_u32(&self, name: &str) -> Option<u32> {
+        let location = self.checked_location(name, "get_uniform_u32", &[gl::UNSIGNED_INT, gl::BOOL])?;
+        let mut value = 0;
+        unsafe{ gl_function(|| gl::GetUniformuiv(self.index(), location, &mut value)) };
+        Some(value)
+    }
+
+    pub fn get_uniform_vec2(&self, name: &str) -> Option<[f32; 2]> {
+        self.get_uniform_floats(name, "get_uniform_vec2", &[gl::FLOAT_VEC2])
+    }
+
+    pub fn get_uniform_vec3(&self, name: &str) -> Option<[f32; 3]> {
+        self.get_uniform_floats(name, "get_uniform_vec3", &[gl::FLOAT_VEC3])
+    }
+
+    pub fn get_uniform_vec4(&self, name: &str) -> Option<[f32; 4]> {
+        self.get_uniform_floats(name, "get_uniform_vec4", &[gl::FLOAT_VEC4])
+    }
+
+    /// Returns the matrix as four columns, as given to 'set_uniform_mat4'
+    pub fn get_uniform_mat4(&self, name: &str) -> Option<[[f32; 4]; 4]> {
+        let values: [f32; 16] = self.get_uniform_floats(name, "get_uniform_mat4", &[gl::FLOAT_MAT4])?;
+        Some(std::array::from_fn(|column| std::array::from_fn(|row| values[column * 4 + row])))
+    }
+
+    fn get_uniform_floats<const N: usize>(&self, name: &str, getter: &str, accepted: &[GLenum]) -> Option<[f32; N]> {
+        let location = self.checked_location(name, getter, accepted)?;
+        let mut values = [0.0; N];
+        unsafe{ gl_function(|| gl::GetUniformfv(self.index(), location, values.as_mut_ptr())) };
+        Some(values)
+    }
+
+    /// As 'checked_location', additionally checking (when validating) that 'count' elements
+    /// fit within the array - GL silently ignores the elements past the end
+    fn checked_array_location(&self, name: &str, setter: &str, accepted: &[GLenum], count: usize) -> Option<GLint> {
+        let location = self.checked_location(name, setter, accepted)?;
+
+        if const { validation::should_validate() } {
+            let size = self.uniform_info(name).map_or(0, |info| info.size) as usize;
+            if count > size {
+                let message = format!("Uniform '{}' has {} element(s) but {} were set with '{}'", name, size, count, setter);
+                log::error!("{}", message);
+                panic!("{}", message);
+            }
+        }
+
+        Some(location)
+    }
+
+    /// Returns the location of the named uniform, or 'None' if it isn't active (e.g. the
+    /// compiler optimised it away) in which case setting it is silently skipped, as in GL
+    ///
+    /// When validating, the uniform's declared type must be one of 'accepted', otherwise
+    /// this panics naming the uniform, its type and the setter (or getter) used
+    fn checked_location(&self, name: &str, setter: &str, accepted: &[GLenum]) -> Option<GLint> {
+        let info = self.uniform_info(name)?;
+
+        // 'should_validate' is a compile time check, so in release this is just a lookup
+        if const { validation::should_validate() } && !accepted.contains(&info.gl_type) {
+            let message = format!(
+                "Uniform '{}' is declared as '{}' but was used with '{}'",
+                name,
+                type_name(info.gl_type),
+                setter
+            );
+            log::error!("{}", message);
+            panic!("{}", message);
+        }
+
+        Some(info.location)
+    }
+
+    /// When validating, checks this is the current program, which 'glUniform*' calls apply to
+    fn check_bound(&self, setter: &str) {
+        if const { validation::should_validate() } && current_program() != self.index() {
+            let message = format!("'{}' requires program {} to be bound, but {} is current", setter, self.index(), current_program());
+            log::error!("{}", message);
+            panic!("{}", message);
+        }
+    }
+
+    /// When validating, checks 'location' came from this program and its type is one of 'accepted'
+    fn checked_location_at(&self, location: UniformLocation, setter: &str, accepted: &[GLenum]) -> GLint {
+        if const { validation::should_validate() } {
+            if location.program != self.index() {
+                let message = format!("'{}' was given a location from program {}, not {}", setter, location.program, self.index());
+                log::error!("{}", message);
+                panic!("{}", message);
+            }
+
+            if location.is_active() && !accepted.contains(&location.gl_type) {
+                let message = format!(
+                    "Uniform at location {} is declared as '{}' but was used with '{}'",
+                    location.location,
+                    type_name(location.gl_type),
+                    setter
+                );
+                log::error!("{}", message);
+                panic!("{}", message);
+            }
+        }
+
+        location.location
+    }
+
+    fn uniform_info(&self, name: &str) -> Option<UniformInfo> {
+        let mut cache = self.uniforms.0.borrow_mut();
+        let uniforms = cache.get_or_insert_with(|| reflect_uniforms(self.index()));
+
+        if let Some(info) = uniforms.get(name) {
+            return Some(*info);
+        }
+
+        // Only the first element of an array is reflected, so look up other elements
+        // (e.g. 'lights[2]') by location with the type of the array
+        let (base, index) = name.strip_suffix(']')?.rsplit_once('[')?;
+        let index: GLint = index.parse().ok()?;
+        let array = *uniforms.get(base)?;
+        let location = uniform_location(self.index(), name)?;
+
+        let info = UniformInfo { location, gl_type: array.gl_type, size: array.size - index };
+        uniforms.insert(name.to_owned(), info);
+        Some(info)
+    }
+}
+
+fn uniform_location(program: GLuint, name: &str) -> Option<GLint> {
+    let name = CString::new(name).ok()?;
+    let mut location = -1;
+    unsafe{ gl_function(|| location = gl::GetUniformLocation(program, name.as_ptr())) };
+    (location >= 0).then_some(location)
+}
+
+fn reflect_uniforms(program: GLuint) -> HashMap<String, UniformInfo> {
+    let mut count = 0;
+    let mut max_length = 0;
+    unsafe{
+        gl_function(|| gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count));
+        gl_function(|| gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length));
+    }
+
+    let mut uniforms = HashMap::with_capacity(count as usize);
+    for index in 0..count as GLuint {
+        let mut name: Vec<u8> = vec![0; max_length as usize];
+        let mut length = 0;
+        let mut size = 0;
+        let mut gl_type = 0;
+        unsafe{
+            gl_function(|| gl::GetActiveUniform(
+                program,
+                index,
+                max_length,
+                &mut length,
+                &mut size,
+                &mut gl_type,
+                name.as_mut_ptr() as _
+            ));
+        }
+        name.truncate(length as usize);
+        let name = String::from_utf8_lossy(&name).into_owned();
+
+        // Members of uniform blocks have no location and can't be set individually
+        let Some(location) = uniform_location(program, &name) else { continue };
+        let info = UniformInfo { location, gl_type, size };
+
+        // Arrays are reported as 'name[0]', which may also be referred to as just 'name'
+        if let Some(base) = name.strip_suffix("[0]") {
+            uniforms.insert(base.to_owned(), info);
+        }
+        uniforms.insert(name, info);
+    }
+
+    uniforms
+}
+
+/// The GLSL name of a uniform type, for error messages
+fn type_name(gl_type: GLenum) -> &'static str {
+    match gl_type {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        gl::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        _ if INTEGER_TYPES.contains(&gl_type) => "sampler",
+        _ => "unknown type",
+    }
+}
\ No newline at end of file
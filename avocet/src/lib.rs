@@ -1,9 +1,44 @@
 pub mod graphics;
 pub mod geometry;
+pub mod math;
 pub mod validation;
 pub mod config;
 pub mod version;
 
+#[cfg(test)]
+mod tests;
+
+/// Loads the OpenGL function pointers, clearing cached queries (the version and limits)
+/// which belong to the previous context, and marks the calling thread as having a current
+/// context - see 'validation::set_context_current'
+fn load_gl_functions<F: FnMut(&str) -> *const std::ffi::c_void>(loader: F) {
+    gl::load_with(loader);
+    version::clear_cached_queries();
+    validation::set_context_current(true);
+}
+
+/// Prepares avocet for use with a context created by any windowing library (GLFW, SDL, winit...)
+///
+/// Make the context current, then pass its function loader, e.g. GLFW's 'get_proc_address' or
+/// SDL's 'gl_get_proc_address'. Avocet itself has no windowing dependency, so this is the only
+/// setup required. Fails if the loader found no functions, typically because no context is current.
+///
+/// Call this again after switching to a context whose function pointers may differ, such as
+/// one recreated after a reset
+pub fn init<F: FnMut(&str) -> *const std::ffi::c_void>(loader: F) -> std::io::Result<()> {
+    load_gl_functions(loader);
+
+    if !gl::GetString::is_loaded() {
+        validation::set_context_current(false);
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No OpenGL functions were loaded, is a context current?"
+        ));
+    }
+
+    Ok(())
+}
+
 #[macro_export]
 macro_rules! const_assert {
     ($cond:expr) => { const _: () = assert!($cond); };
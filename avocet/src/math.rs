@@ -0,0 +1,73 @@
+/// A 4x4 matrix of 'f32' stored as columns, matching GLSL's 'mat4' layout
+///
+/// Vectors are treated as columns, so 'a * b' applies 'b' first and then 'a'
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4 {
+    pub columns: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self::from_columns([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub const fn from_columns(columns: [[f32; 4]; 4]) -> Self { Self { columns } }
+
+    pub const fn translation(x: f32, y: f32, z: f32) -> Self {
+        Self::from_columns([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [x, y, z, 1.0],
+        ])
+    }
+
+    pub const fn scale(x: f32, y: f32, z: f32) -> Self {
+        Self::from_columns([
+            [x, 0.0, 0.0, 0.0],
+            [0.0, y, 0.0, 0.0],
+            [0.0, 0.0, z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// A counter-clockwise rotation about the z axis, in radians
+    pub fn rotation_z(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::from_columns([
+            [cos, sin, 0.0, 0.0],
+            [-sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Transforms the point (x, y, z, 1)
+    #[must_use]
+    pub fn transform_point(&self, point: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = point;
+        std::array::from_fn(|row| {
+            self.columns[0][row] * x + self.columns[1][row] * y + self.columns[2][row] * z + self.columns[3][row]
+        })
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self { Self::IDENTITY }
+}
+
+impl std::ops::Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let columns = std::array::from_fn(|column| {
+            std::array::from_fn(|row| {
+                (0..4).map(|k| self.columns[k][row] * rhs.columns[column][k]).sum()
+            })
+        });
+        Self::from_columns(columns)
+    }
+}
\ No newline at end of file
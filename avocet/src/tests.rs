@@ -0,0 +1,293 @@
+use crate::{
+    config::{BuildMode, TargetPlatform},
+    geometry::{self, VertexAttribute, VertexLayout},
+    graphics,
+    math::Mat4,
+    validation::{self, ValidationMode},
+    version::{self, OpenGLVersion},
+};
+
+#[cfg(not(feature = "force-validation"))]
+#[test]
+fn release_build_disables_validation() {
+    if crate::config::is_release_mode() {
+        assert_eq!(validation::validation_mode(), ValidationMode::None);
+    } else {
+        assert_ne!(validation::validation_mode(), ValidationMode::None);
+    }
+}
+
+#[test]
+fn release_validation_mode_is_none_on_every_platform() {
+    for platform in [TargetPlatform::Windows, TargetPlatform::Mac, TargetPlatform::Linux] {
+        assert_eq!(validation::select_validation_mode(platform, BuildMode::Release), ValidationMode::None);
+    }
+}
+
+#[test]
+fn debug_validation_mode_per_platform() {
+    assert_eq!(validation::select_validation_mode(TargetPlatform::Windows, BuildMode::Debug), ValidationMode::Advanced);
+    assert_eq!(validation::select_validation_mode(TargetPlatform::Mac, BuildMode::Debug), ValidationMode::Basic);
+    assert_eq!(validation::select_validation_mode(TargetPlatform::Linux, BuildMode::Debug), ValidationMode::Dynamic);
+}
+
+#[cfg(feature = "force-validation")]
+#[test]
+fn forced_validation_is_advanced() {
+    assert_eq!(validation::validation_mode(), ValidationMode::Advanced);
+}
+
+#[cfg(not(feature = "force-validation"))]
+#[test]
+fn validation_mode_matches_current_configuration() {
+    assert_eq!(
+        validation::validation_mode(),
+        validation::select_validation_mode(crate::config::target_platform(), crate::config::build_mode())
+    );
+}
+
+/// Clears the validation mode override when dropped, so a failing assert can't leave it set
+/// for tests running afterwards on other threads
+struct ClearOverrideOnDrop;
+
+impl Drop for ClearOverrideOnDrop {
+    fn drop(&mut self) { validation::clear_validation_mode_override(); }
+}
+
+#[test]
+fn validation_mode_override() {
+    let _clear = ClearOverrideOnDrop;
+
+    validation::set_validation_mode(ValidationMode::Basic);
+    if validation::validation_mode() == ValidationMode::None {
+        assert_eq!(validation::active_validation_mode(), ValidationMode::None);
+    } else {
+        assert_eq!(validation::active_validation_mode(), ValidationMode::Basic);
+    }
+
+    validation::clear_validation_mode_override();
+    assert_eq!(validation::active_validation_mode(), validation::validation_mode());
+}
+
+#[test]
+fn display_opengl_version() {
+    assert_eq!(OpenGLVersion { major: 4, minor: 6 }.to_string(), "4.6");
+    assert_eq!(OpenGLVersion { major: 3, minor: 3 }.to_string(), "3.3");
+    assert_eq!(OpenGLVersion::latest().to_string(), "4.6");
+}
+
+#[test]
+fn display_configuration() {
+    assert_eq!(BuildMode::Debug.to_string(), "debug");
+    assert_eq!(BuildMode::Release.to_string(), "release");
+
+    assert_eq!(TargetPlatform::Windows.to_string(), "Windows");
+    assert_eq!(TargetPlatform::Mac.to_string(), "Mac");
+    assert_eq!(TargetPlatform::Linux.to_string(), "Linux");
+
+    assert_eq!(ValidationMode::None.to_string(), "none");
+    assert_eq!(ValidationMode::Basic.to_string(), "basic (glGetError)");
+    assert_eq!(ValidationMode::Advanced.to_string(), "advanced (debug message log)");
+    assert_eq!(ValidationMode::Dynamic.to_string(), "dynamic (chosen at runtime)");
+}
+
+#[test]
+fn opengl_version_ordering() {
+    let version = OpenGLVersion::new;
+
+    assert!(version(3, 3) < version(4, 1));
+    assert!(version(4, 1) < version(4, 6));
+    assert!(version(4, 0) > version(3, 9));
+    assert_eq!(version(4, 3), version(4, 3));
+    assert_eq!(OpenGLVersion::latest(), version(4, 6));
+}
+
+#[test]
+fn opengl_version_at_least() {
+    let version = OpenGLVersion { major: 4, minor: 1 };
+
+    assert!(version.at_least(3, 3));
+    assert!(version.at_least(4, 0));
+    assert!(version.at_least(4, 1));
+    assert!(!version.at_least(4, 3));
+    assert!(!version.at_least(5, 0));
+
+    const MAC: OpenGLVersion = OpenGLVersion { major: 4, minor: 1 };
+    const _: () = assert!(MAC.at_least(3, 3));
+}
+
+#[test]
+fn debug_message_log_requires_4_3() {
+    assert!(!OpenGLVersion { major: 3, minor: 3 }.supports_debug_message_log());
+    assert!(!OpenGLVersion { major: 4, minor: 1 }.supports_debug_message_log());
+    assert!(OpenGLVersion { major: 4, minor: 3 }.supports_debug_message_log());
+    assert!(OpenGLVersion { major: 4, minor: 6 }.supports_debug_message_log());
+    assert!(OpenGLVersion { major: 5, minor: 0 }.supports_debug_message_log());
+}
+
+#[test]
+fn mat4_transforms_points() {
+    let point = [1.0, 2.0, 3.0];
+
+    assert_eq!(Mat4::IDENTITY.transform_point(point), point);
+    assert_eq!(Mat4::translation(1.0, -1.0, 0.5).transform_point(point), [2.0, 1.0, 3.5]);
+    assert_eq!(Mat4::scale(2.0, 3.0, 4.0).transform_point(point), [2.0, 6.0, 12.0]);
+
+    let rotated = Mat4::rotation_z(std::f32::consts::FRAC_PI_2).transform_point([1.0, 0.0, 0.0]);
+    assert!((rotated[0] - 0.0).abs() < 1e-6 && (rotated[1] - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn mat4_multiplication_applies_right_first() {
+    let translate = Mat4::translation(1.0, 0.0, 0.0);
+    let scale = Mat4::scale(2.0, 2.0, 2.0);
+
+    assert_eq!((translate * scale).transform_point([1.0, 1.0, 1.0]), [3.0, 2.0, 2.0]);
+    assert_eq!((scale * translate).transform_point([1.0, 1.0, 1.0]), [4.0, 2.0, 2.0]);
+    assert_eq!(translate * Mat4::IDENTITY, translate);
+}
+
+#[test]
+fn vertex_layout_packs_attributes() {
+    #[allow(dead_code)]
+    #[repr(C)]
+    struct Vertex { position: [f32; 3], normal: [f32; 3], uv: [f32; 2] }
+
+    let layout = VertexLayout::new().attribute(0, 3).attribute(1, 3).per_instance_attribute(2, 2, 1);
+
+    assert_eq!(layout.stride(), std::mem::size_of::<Vertex>());
+    assert_eq!(layout.attributes()[1], VertexAttribute { location: 1, components: 3, offset: 12, divisor: 0 });
+    assert_eq!(layout.attributes()[2].offset, 24);
+    assert_eq!(layout.attributes()[2].divisor, 1);
+}
+
+#[test]
+fn layout_offsets_are_packed() {
+    let attribute = |components| VertexAttribute { location: 0, components, offset: 99, divisor: 0 };
+    let (offsets, stride) = geometry::compute_layout_offsets(&[attribute(3), attribute(2), attribute(4)]);
+
+    assert_eq!(offsets, [0, 12, 20]);
+    assert_eq!(stride, 36);
+    assert_eq!(geometry::compute_layout_offsets(&[]), (Vec::new(), 0));
+}
+
+#[test]
+fn parse_version_strings() {
+    assert_eq!(version::parse_version("4.6.0 NVIDIA 535.54.03"), Some(OpenGLVersion::new(4, 6)));
+    assert_eq!(version::parse_version("4.1 Metal - 88"), Some(OpenGLVersion::new(4, 1)));
+    assert_eq!(version::parse_version("OpenGL ES 3.2 Mesa 23.0.4"), Some(OpenGLVersion::new(3, 2)));
+    assert_eq!(version::parse_version("10.12"), Some(OpenGLVersion::new(10, 12)));
+    assert_eq!(version::parse_version("unknown"), None);
+}
+
+#[test]
+fn glsl_versions_follow_opengl() {
+    assert_eq!(version::glsl_version_string(OpenGLVersion::new(4, 6)), "460 core");
+    assert_eq!(version::glsl_version_string(OpenGLVersion::new(4, 1)), "410 core");
+    assert_eq!(version::glsl_version_string(OpenGLVersion::new(3, 3)), "330 core");
+    assert_eq!(version::glsl_version_string(OpenGLVersion::new(3, 2)), "150 core");
+    assert_eq!(version::glsl_version_string(OpenGLVersion::new(2, 1)), "120");
+}
+
+#[test]
+fn fallback_sample_counts_are_powers_of_two() {
+    assert_eq!(version::fallback_sample_counts(8), [8, 4, 2]);
+    assert_eq!(version::fallback_sample_counts(6), [4, 2]);
+    assert!(version::fallback_sample_counts(1).is_empty());
+}
+
+#[test]
+fn packed_texture_lengths_detect_overflow() {
+    assert_eq!(graphics::packed_length(4, &[2, 3]), Some(24));
+    assert_eq!(graphics::packed_length(4, &[2, 3, 5]), Some(120));
+
+    // 1024 cubed RGBA8 wraps to zero in 32-bit arithmetic
+    assert_eq!(graphics::packed_length(4, &[1024, 1024, 1024]), Some(1 << 32));
+    assert_eq!(graphics::packed_length(4, &[u32::MAX, u32::MAX, u32::MAX]), None);
+}
+
+#[test]
+fn decode_greyscale_netpbm() {
+    let bytes = b"P5\n# A comment\n3 1\n255\n\x00\x80\xff";
+    let image = graphics::decode_image(bytes).unwrap();
+
+    assert_eq!((image.width, image.height), (3, 1));
+    assert_eq!(image.format, graphics::TextureFormat::R8);
+    assert_eq!(image.data, [0x00, 0x80, 0xff]);
+}
+
+#[test]
+fn decode_detects_channels() {
+    let encode = |pixel: &[u8], color| {
+        let mut png = Vec::new();
+        image::ImageEncoder::write_image(image::codecs::png::PngEncoder::new(&mut png), pixel, 1, 1, color).unwrap();
+        graphics::decode_image(&png).unwrap()
+    };
+
+    let luma_alpha = encode(&[0x40, 0x80], image::ExtendedColorType::La8);
+    assert_eq!((luma_alpha.format, luma_alpha.data), (graphics::TextureFormat::RG8, vec![0x40, 0x80]));
+
+    let rgba = encode(&[1, 2, 3, 4], image::ExtendedColorType::Rgba8);
+    assert_eq!((rgba.format, rgba.data), (graphics::TextureFormat::RGBA8, vec![1, 2, 3, 4]));
+
+    // 16-bit channels are reduced to 8 bits
+    let rgb16 = encode(&[0xff, 0xff, 0, 0, 0, 0], image::ExtendedColorType::Rgb16);
+    assert_eq!((rgb16.format, rgb16.data), (graphics::TextureFormat::RGB8, vec![0xff, 0, 0]));
+}
+
+#[test]
+fn decode_invalid_images_fails() {
+    let error = graphics::decode_image(b"P6 2 2 255\n\x00\x00\x00").err().unwrap();
+    assert!(matches!(error, graphics::TextureError::Decode(_)));
+
+    let error = graphics::decode_image(b"Not an image").err().unwrap();
+    assert!(matches!(error, graphics::TextureError::UnsupportedFormat));
+
+    // A crafted header is rejected rather than overflowing the data length
+    assert!(graphics::decode_image(b"P6 4294967295 4294967295 255\n").is_err());
+}
+
+#[test]
+fn defines_follow_version_directive() {
+    let source = "#version 330 core\nvoid main() {}";
+    let defines = [("USE_NORMAL_MAP".to_owned(), "1".to_owned()), ("LIGHTS".to_owned(), "4".to_owned())];
+
+    assert_eq!(
+        graphics::inject_defines(source, &defines),
+        "#version 330 core\n#define USE_NORMAL_MAP 1\n#define LIGHTS 4\n#line 2\nvoid main() {}"
+    );
+}
+
+#[test]
+fn defines_without_version_directive_come_first() {
+    let defines = [("FOO".to_owned(), "".to_owned())];
+    assert_eq!(graphics::inject_defines("void main() {}", &defines), "#define FOO \n#line 1\nvoid main() {}");
+}
+
+#[test]
+fn defines_after_leading_comments() {
+    let source = "// A shader\n#version 410\nvoid main() {}";
+    let defines = [("FOO".to_owned(), "2".to_owned())];
+    assert_eq!(
+        graphics::inject_defines(source, &defines),
+        "// A shader\n#version 410\n#define FOO 2\n#line 3\nvoid main() {}"
+    );
+}
+
+#[test]
+fn empty_screenshots_are_rejected() {
+    // Fails before reading any pixels, so no context is needed
+    let path = std::env::temp_dir().join("avocet_empty_screenshot.png");
+    assert_eq!(graphics::save_screenshot(&path, 0, 16).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(graphics::save_screenshot(&path, 16, 0).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+// Without a context any error checking would call unloaded GL functions and panic,
+// so this only passes if 'gl_function' does nothing beyond the wrapped call
+#[cfg(all(not(debug_assertions), not(feature = "force-validation")))]
+#[test]
+fn release_gl_function_only_invokes_wrapped_call() {
+    let mut calls = 0;
+    validation::gl_function(|| calls += 1);
+    assert_eq!(calls, 1);
+}
\ No newline at end of file
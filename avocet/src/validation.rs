@@ -1,9 +1,18 @@
 use crate::{
-    config,
+    config::{self, BuildMode, TargetPlatform},
     version,
 };
 
-use gl::types::GLint;
+use gl::types::GLenum;
+
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Mutex,
+    },
+};
+
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(u8)]
@@ -17,22 +26,101 @@ pub enum ValidationMode {
 impl ValidationMode {
     /// Currently, Rust's traits don't support const functions
     pub const fn equals(self, rhs: ValidationMode) -> bool { self as u8 == rhs as u8 }
+
+    const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ValidationMode::None),
+            1 => Some(ValidationMode::Basic),
+            2 => Some(ValidationMode::Advanced),
+            3 => Some(ValidationMode::Dynamic),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValidationMode::None => "none",
+            ValidationMode::Basic => "basic (glGetError)",
+            ValidationMode::Advanced => "advanced (debug message log)",
+            ValidationMode::Dynamic => "dynamic (chosen at runtime)",
+        };
+        write!(f, "{}", name)
+    }
 }
 
-/// Returns the validation mode based on platform and build configuration
+/// Returns the validation mode based on platform and build configuration, or 'Advanced'
+/// when the 'force-validation' feature is enabled
 pub const fn validation_mode() -> ValidationMode {
-    if config::is_release_mode() {
-        ValidationMode::None
-    } else if config::is_windows() {
-        ValidationMode::Advanced
-    } else if config::is_mac() {
-        ValidationMode::Basic
-    } else {
-        ValidationMode::Dynamic
+    if cfg!(feature = "force-validation") {
+        return ValidationMode::Advanced;
+    }
+
+    select_validation_mode(config::target_platform(), config::build_mode())
+}
+
+/// The validation mode used for a given platform and build configuration:
+/// - release builds never validate
+/// - Windows drivers support the debug message log, so use it ('Advanced')
+/// - Mac is capped at OpenGL 4.1, which predates the debug message log ('Basic')
+/// - Linux depends on the driver, so decide at runtime ('Dynamic')
+pub const fn select_validation_mode(platform: TargetPlatform, build: BuildMode) -> ValidationMode {
+    if build.equals(BuildMode::Release) {
+        return ValidationMode::None;
+    }
+
+    match platform {
+        TargetPlatform::Windows => ValidationMode::Advanced,
+        TargetPlatform::Mac => ValidationMode::Basic,
+        TargetPlatform::Linux => ValidationMode::Dynamic,
     }
 }
 
-const fn should_validate() -> bool { !validation_mode().equals(ValidationMode::None) }
+pub(crate) const fn should_validate() -> bool { !validation_mode().equals(ValidationMode::None) }
+
+/// 'u8::MAX' when there's no override
+static VALIDATION_MODE_OVERRIDE: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Overrides the validation mode at runtime, e.g. to only enable heavy checking while
+/// investigating a problem
+///
+/// Validation is compiled out of builds where 'validation_mode' is 'None' (i.e. release), so
+/// there this has no effect. Errors raised while validation was off are discarded, rather
+/// than being reported against the next call.
+pub fn set_validation_mode(mode: ValidationMode) {
+    if const { !should_validate() } {
+        log::warn!("Validation is disabled in this build, ignoring the requested '{}' mode", mode);
+        return;
+    }
+
+    if is_context_current() {
+        let _ = check_for_basic_errors();
+        drain_debug_messages();
+    }
+
+    VALIDATION_MODE_OVERRIDE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Returns to the mode chosen by 'validation_mode'
+pub fn clear_validation_mode_override() {
+    VALIDATION_MODE_OVERRIDE.store(u8::MAX, Ordering::Relaxed);
+}
+
+/// The validation mode in use: the runtime override if set, otherwise 'validation_mode'
+#[must_use]
+pub fn active_validation_mode() -> ValidationMode {
+    if const { !should_validate() } {
+        return ValidationMode::None;
+    }
+
+    ValidationMode::from_u8(VALIDATION_MODE_OVERRIDE.load(Ordering::Relaxed)).unwrap_or(const { validation_mode() })
+}
+
+// Release builds must never validate - this guarantees 'gl_function' compiles down to just
+// the wrapped call, so a refactor introducing a runtime branch fails to build
+#[cfg(all(not(debug_assertions), not(feature = "force-validation")))]
+crate::const_assert!(!should_validate());
 
 // ------------------------------------------------------------------------------------------
 
@@ -48,12 +136,13 @@ enum ErrorCode {
     StackOverflow = gl::STACK_OVERFLOW,
     StackUnderflow = gl::STACK_UNDERFLOW,
     OutOfMemory = gl::OUT_OF_MEMORY,
+    ContextLost = gl::CONTEXT_LOST,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(u32)]
-enum DebugSource {
+pub enum DebugSource {
     API = gl::DEBUG_SOURCE_API,
     WindowSystem = gl::DEBUG_SOURCE_WINDOW_SYSTEM,
     ShaderCompiler = gl::DEBUG_SOURCE_SHADER_COMPILER,
@@ -65,7 +154,7 @@ enum DebugSource {
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(u32)]
-enum DebugType {
+pub enum DebugType {
     Error = gl::DEBUG_TYPE_ERROR,
     DeprecatedBehaviour = gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR,
     UndefinedBehaviour = gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR,
@@ -80,36 +169,106 @@ enum DebugType {
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(u32)]
-enum DebugSeverity {
+pub enum DebugSeverity {
     High = gl::DEBUG_SEVERITY_HIGH,
     Medium = gl::DEBUG_SEVERITY_MEDIUM,
     Low = gl::DEBUG_SEVERITY_LOW,
     Notification = gl::DEBUG_SEVERITY_NOTIFICATION,
 }
 
-struct DebugInfo {
-    severity: DebugSeverity,
-    message: String,
+/// Enables or disables debug messages matching the filter - 'glDebugMessageControl'
+///
+/// 'None' matches any value ('GL_DONT_CARE'), so for example only errors are reported with:
+/// 'set_debug_filter(None, None, None, false)' followed by 'set_debug_filter(None, Some(DebugType::Error), None, true)'.
+/// Has no effect without a debug context.
+pub fn set_debug_filter(source: Option<DebugSource>, debug_type: Option<DebugType>, severity: Option<DebugSeverity>, enabled: bool) {
+    if !gl::DebugMessageControl::is_loaded() {
+        return;
+    }
+
+    let source = source.map_or(gl::DONT_CARE, |source| source as GLenum);
+    let debug_type = debug_type.map_or(gl::DONT_CARE, |debug_type| debug_type as GLenum);
+    let severity = severity.map_or(gl::DONT_CARE, |severity| severity as GLenum);
+    let enabled = if enabled { gl::TRUE } else { gl::FALSE };
+    unsafe{ gl::DebugMessageControl(source, debug_type, severity, 0, std::ptr::null(), enabled) };
 }
 
-fn max_message_length() -> usize {
-    static mut MAX_DEBUG_MESSAGE_LENGTH: Option<GLint> = None;
-    // This branch allows us to only retrieve the value if we haven't already
-    if let None = unsafe{ MAX_DEBUG_MESSAGE_LENGTH } {
-        let mut length = 0;
-        unsafe{ gl::GetIntegerv(gl::MAX_DEBUG_MESSAGE_LENGTH, &mut length); }
-        unsafe{ MAX_DEBUG_MESSAGE_LENGTH = Some(length) };
+/// Enables or disables specific debug messages by id, e.g. a driver's noisy performance warning
+///
+/// Ids are only unique for a given source and type, so both must be specified
+pub fn set_debug_filter_ids(source: DebugSource, debug_type: DebugType, ids: &[u32], enabled: bool) {
+    if !gl::DebugMessageControl::is_loaded() {
+        return;
     }
 
-    if let Some(length) = unsafe{ MAX_DEBUG_MESSAGE_LENGTH } {
-        length as usize
-    } else {
-        unreachable!()
+    let enabled = if enabled { gl::TRUE } else { gl::FALSE };
+    unsafe{
+        gl::DebugMessageControl(source as GLenum, debug_type as GLenum, gl::DONT_CARE, ids.len() as _, ids.as_ptr(), enabled)
+    };
+}
+
+static IGNORED_MESSAGE_IDS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Drops debug messages with the given ids, replacing any previously ignored ids
+///
+/// Unlike 'set_debug_filter_ids' this doesn't need the message's source and type, and works
+/// however the message reaches the log, e.g. NVIDIA's recurring buffer memory notification (131185).
+/// Ignored messages are neither reported by validation nor returned by 'drain_debug_messages'.
+pub fn set_ignored_message_ids(ids: &[u32]) {
+    *IGNORED_MESSAGE_IDS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = ids.to_vec();
+}
+
+fn is_ignored(message: &DebugMessage) -> bool {
+    IGNORED_MESSAGE_IDS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains(&message.id)
+}
+
+/// A message from the debug message log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugMessage {
+    pub source: DebugSource,
+    pub debug_type: DebugType,
+    pub severity: DebugSeverity,
+    /// Identifies the message within its source and type, see 'set_debug_filter_ids'
+    pub id: u32,
+    pub text: String,
+}
+
+impl std::fmt::Display for DebugMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Source: {:?}; Type: {:?}; Severity: {:?}; Id: {}\n{}",
+            self.source,
+            self.debug_type,
+            self.severity,
+            self.id,
+            self.text
+        )
     }
 }
 
+/// The limit is 0 without debug support, which would leave no room to receive a message
+const MIN_MESSAGE_LENGTH: usize = 256;
+
+fn max_message_length() -> usize {
+    version::get_opengl_limits().max_debug_message_length.max(MIN_MESSAGE_LENGTH)
+}
+
+/// Whether messages are being written to the debug message log - 'GL_DEBUG_OUTPUT'
+///
+/// Only debug contexts enable this by default. The limit is only queried when the debug
+/// message log is supported, so a non-zero limit also guards against 'GL_INVALID_ENUM'
+fn debug_output_enabled() -> bool {
+    version::get_opengl_limits().max_debug_message_length > 0 &&
+        unsafe{ gl::IsEnabled(gl::DEBUG_OUTPUT) } == gl::TRUE
+}
+
 #[must_use]
-fn get_next_message() -> Option<DebugInfo> {
+fn get_next_message() -> Option<DebugMessage> {
+    if !debug_output_enabled() {
+        return None;
+    }
+
     let mut message: Vec<u8> = Vec::with_capacity(max_message_length());
     let mut source = 0;
     let mut debug_type = 0;
@@ -135,7 +294,8 @@ fn get_next_message() -> Option<DebugInfo> {
     }
 
     if message_count > 0 {
-        let message = unsafe{ String::from_utf8_unchecked(message) };
+        // Drivers aren't guaranteed to produce valid UTF-8, and the length includes the terminator
+        let text = String::from_utf8_lossy(&message).trim_end_matches('\0').to_owned();
 
         // I'll add an explanation for these 'std::mem::transmute's into the notes document
         //
@@ -144,16 +304,7 @@ fn get_next_message() -> Option<DebugInfo> {
         let debug_type: DebugType = unsafe{ std::mem::transmute(debug_type) };
         let severity: DebugSeverity = unsafe{ std::mem::transmute(severity) };
 
-        Some(DebugInfo {
-            severity,
-            message: format!(
-                "Source: {:?}; Type: {:?}; Severity: {:?}\n{}",
-                source,
-                debug_type,
-                severity,
-                message
-            ),
-        })
+        Some(DebugMessage { source, debug_type, severity, id, text })
     } else {
         None
     }
@@ -161,7 +312,7 @@ fn get_next_message() -> Option<DebugInfo> {
 
 // ------------------------------------------------------------------------------------------
 
-fn check_for_basic_errors() {
+fn check_for_basic_errors() -> Result<(), GlError> {
     let mut message = String::new();
     loop {
         let error_code: ErrorCode = unsafe{ std::mem::transmute(gl::GetError()) };
@@ -173,51 +324,235 @@ fn check_for_basic_errors() {
     }    
 
     if !message.is_empty() {
-        panic!("{}", message);
+        Err(GlError { message })
+    } else {
+        Ok(())
     }
 }
 
-fn check_for_advanced_errors() {
+/// Removes and returns every message currently in the debug message log
+///
+/// Unlike validation, this never panics: it lets applications show or log messages themselves,
+/// e.g. in an overlay. Note validation drains the log after each call when using the
+/// debug message log, so call this with validation disabled or use it for messages
+/// produced outside 'gl_function' (such as those inserted by other libraries).
+pub fn drain_debug_messages() -> Vec<DebugMessage> {
+    std::iter::from_fn(get_next_message).filter(|message| !is_ignored(message)).collect()
+}
+
+static PRINT_NOTIFICATIONS: AtomicBool = AtomicBool::new(false);
+
+/// Enables logging of notification severity debug messages, these are dropped by default
+/// as some drivers emit them constantly (e.g. buffer allocation notifications)
+pub fn set_print_notifications(enabled: bool) {
+    PRINT_NOTIFICATIONS.store(enabled, Ordering::Relaxed);
+}
+
+fn check_for_advanced_errors() -> Result<(), GlError> {
+    // Without debug output nothing reaches the log, so fall back rather than miss errors
+    if !debug_output_enabled() {
+        return check_for_basic_errors();
+    }
+
     let mut message = String::new();
-    while let Some(debug_info) = get_next_message() {
-        if let DebugSeverity::Notification = debug_info.severity {
-            eprintln!("{}", debug_info.message);
+    while let Some(debug_message) = get_next_message() {
+        if is_ignored(&debug_message) {
+            continue;
+        }
+
+        if let DebugSeverity::Notification = debug_message.severity {
+            if PRINT_NOTIFICATIONS.load(Ordering::Relaxed) {
+                log::debug!("{}", debug_message);
+            }
         } else {
-            message.push_str(&debug_info.message);
+            message.push_str(&debug_message.to_string());
             message.push('\n');
         }
     }
 
     if !message.is_empty() {
-        panic!("{}", message);
+        Err(GlError { message })
+    } else {
+        Ok(())
     }
 }
 
 // ------------------------------------------------------------------------------------------
 
-fn check_for_errors() {
-    let validation_mode = const { validation_mode() };
-    match validation_mode {
+/// The error(s) reported by OpenGL after a call, as collected by the validation mode in use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlError {
+    message: String,
+}
+
+impl GlError {
+    #[must_use] pub fn message(&self) -> &str { &self.message }
+}
+
+impl std::fmt::Display for GlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for GlError {}
+
+/// Why a context was reset, as reported by 'glGetGraphicsResetStatus'
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u32)]
+pub enum ResetStatus {
+    /// This context caused the reset
+    Guilty = gl::GUILTY_CONTEXT_RESET,
+    /// Another context caused the reset
+    Innocent = gl::INNOCENT_CONTEXT_RESET,
+    /// The cause is unknown, e.g. a driver update or a GPU switch
+    Unknown = gl::UNKNOWN_CONTEXT_RESET,
+}
+
+static CONTEXT_LOST: AtomicBool = AtomicBool::new(false);
+static CONTEXT_RESET_CALLBACK: Mutex<Option<fn(ResetStatus)>> = Mutex::new(None);
+
+/// Queries whether the context has been reset - 'glGetGraphicsResetStatus'
+///
+/// Requires OpenGL 4.5 or 'GL_KHR_robustness', otherwise this always returns 'None'.
+/// Resets are only reported for contexts created with a 'lose context on reset'
+/// notification strategy.
+pub fn check_context_reset() -> Option<ResetStatus> {
+    if !gl::GetGraphicsResetStatus::is_loaded() {
+        return None;
+    }
+
+    let version = version::get_opengl_version();
+    if !version.at_least(4, 5) && !version::has_extension("GL_KHR_robustness") {
+        return None;
+    }
+
+    match unsafe{ gl::GetGraphicsResetStatus() } {
+        gl::GUILTY_CONTEXT_RESET => Some(ResetStatus::Guilty),
+        gl::INNOCENT_CONTEXT_RESET => Some(ResetStatus::Innocent),
+        gl::UNKNOWN_CONTEXT_RESET => Some(ResetStatus::Unknown),
+        _ => None,
+    }
+}
+
+/// Registers a function to call when validation detects the context has been reset
+///
+/// After a reset every GL object is gone and the context must be recreated along with all
+/// resources. Until 'clear_context_lost' is called, errors are no longer reported so the
+/// lost context doesn't produce a panic on every subsequent call.
+pub fn set_context_reset_callback(callback: fn(ResetStatus)) {
+    *CONTEXT_RESET_CALLBACK.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(callback);
+}
+
+/// Resumes error reporting once the context and its resources have been recreated
+pub fn clear_context_lost() {
+    CONTEXT_LOST.store(false, Ordering::Relaxed);
+}
+
+/// Whether a context reset has been detected and not yet cleared
+#[must_use]
+pub fn is_context_lost() -> bool {
+    CONTEXT_LOST.load(Ordering::Relaxed)
+}
+
+/// Errors after a reset are expected, so rather than reporting them hand over to the callback
+fn handle_context_reset() -> bool {
+    if CONTEXT_LOST.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    if let Some(status) = check_context_reset() {
+        CONTEXT_LOST.store(true, Ordering::Relaxed);
+        log::error!("The OpenGL context was reset ({:?})", status);
+
+        let callback = *CONTEXT_RESET_CALLBACK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(callback) = callback {
+            callback(status);
+        }
+        true
+    } else {
+        false
+    }
+}
+
+thread_local! {
+    static CONTEXT_CURRENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Records whether this thread has a current context, call after making a context current
+/// (or releasing it). 'avocet::init' marks the calling thread as current.
+///
+/// When validating, wrapped GL calls on a thread without a current context panic with
+/// "no current GL context" rather than failing in confusing ways.
+pub fn set_context_current(current: bool) {
+    CONTEXT_CURRENT.with(|flag| flag.set(current));
+}
+
+/// Whether this thread has been marked as having a current context
+#[must_use]
+pub fn is_context_current() -> bool {
+    CONTEXT_CURRENT.with(Cell::get)
+}
+
+fn assert_context_current() {
+    if !is_context_current() {
+        log::error!("no current GL context");
+        panic!("no current GL context");
+    }
+}
+
+fn check_for_errors() -> Result<(), GlError> {
+    match active_validation_mode() {
         ValidationMode::Basic => check_for_basic_errors(),
         ValidationMode::Advanced => check_for_advanced_errors(),
         ValidationMode::Dynamic => {
             let version = version::get_opengl_version();
             if version.supports_debug_message_log() {
-                check_for_advanced_errors();
+                check_for_advanced_errors()
             } else {
-                check_for_basic_errors();
+                check_for_basic_errors()
             }
         },
-        _ => {},
+        _ => Ok(()),
     }
 }
 
 #[inline]
 pub fn gl_function<F: FnMut()>(mut f: F) {
+    // 'should_validate' is a compile time check
+    if const { should_validate() } {
+        assert_context_current();
+    }
+
+    f();
+
+    if const { should_validate() } {
+        if let Err(error) = check_for_errors() {
+            if handle_context_reset() {
+                return;
+            }
+
+            log::error!("{}", error);
+            panic!("{}", error);
+        }
+    }
+}
+
+/// The fallible counterpart to 'gl_function', returning any errors rather than panicking
+///
+/// Note: errors are only checked when validating, so without validation (e.g. in release)
+/// this always returns 'Ok'
+#[inline]
+pub fn try_gl_function<F: FnMut()>(mut f: F) -> Result<(), GlError> {
+    if const { should_validate() } {
+        assert_context_current();
+    }
+
     f();
 
-    // 'should_validate' is a compile time check
     if const { should_validate() } {
-        check_for_errors();
+        check_for_errors()
+    } else {
+        Ok(())
     }
 }
\ No newline at end of file
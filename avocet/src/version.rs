@@ -1,36 +1,176 @@
-#[derive(Debug, Clone, Copy)]
+use std::cell::Cell;
+
+/// Versions are ordered by major then minor version, e.g. 3.3 < 4.1 < 4.6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct OpenGLVersion {
     pub major: usize,
     pub minor: usize,
 }
 
 impl OpenGLVersion {
-    pub fn supports_debug_message_log(&self) -> bool {
-        self.major > 3 && self.minor >= 3
+    pub const fn new(major: usize, minor: usize) -> Self { Self { major, minor } }
+
+    /// Returns true if this version is 'major.minor' or later
+    pub const fn at_least(&self, major: usize, minor: usize) -> bool {
+        self.major > major || (self.major == major && self.minor >= minor)
+    }
+
+    /// 'glGetDebugMessageLog' is core since OpenGL 4.3
+    pub const fn supports_debug_message_log(&self) -> bool {
+        self.at_least(4, 3)
     }
 
     /// Returns the latest possible OpenGL version: 4.6
     pub fn latest() -> Self { Self { major: 4, minor: 6 } }
 }
 
+impl std::fmt::Display for OpenGLVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The GLSL version matching an OpenGL version, as written after '#version', e.g. "460 core"
+///
+/// From OpenGL 3.3 the numbers match; earlier versions have their own, and profiles
+/// (and so 'core') only exist from 3.2
+pub fn glsl_version_string(version: OpenGLVersion) -> String {
+    match (version.major, version.minor) {
+        (2, 0) => "110".to_owned(),
+        (2, _) => "120".to_owned(),
+        (3, 0) => "130".to_owned(),
+        (3, 1) => "140".to_owned(),
+        (3, 2) => "150 core".to_owned(),
+        (major, minor) => format!("{}{}0 core", major, minor),
+    }
+}
+
+// Per thread, as each caches the context current on its thread
+thread_local! {
+    static OPENGL_VERSION: Cell<Option<OpenGLVersion>> = const { Cell::new(None) };
+    static OPENGL_LIMITS: Cell<Option<GlLimits>> = const { Cell::new(None) };
+}
+
+/// Forgets this thread's cached version and limits, which belong to the context they were queried from
+pub(crate) fn clear_cached_queries() {
+    OPENGL_VERSION.with(|version| version.set(None));
+    OPENGL_LIMITS.with(|limits| limits.set(None));
+}
+
+/// Parses the version from a 'GL_VERSION' string, e.g. "4.6.0 NVIDIA 535.54" or
+/// "OpenGL ES 3.2 Mesa 23.0" - the first 'major.minor' pair, ignoring any release number
+pub fn parse_version(version_string: &str) -> Option<OpenGLVersion> {
+    version_string.split_whitespace().find_map(|word| {
+        let mut numbers = word.split('.');
+        let major = numbers.next()?.parse().ok()?;
+        let minor = numbers.next()?.parse().ok()?;
+        Some(OpenGLVersion { major, minor })
+    })
+}
+
 pub fn get_opengl_version() -> OpenGLVersion {
-    static mut OPENGL_VERSION: Option<OpenGLVersion> = None;
-    if let None = unsafe { OPENGL_VERSION } {
-        let version_string = get_opengl_version_string();
-        let verion_bytes = version_string.as_bytes();
+    if let Some(version) = OPENGL_VERSION.with(Cell::get) {
+        return version;
+    }
+
+    let version_string = get_opengl_version_string();
+    let version = parse_version(&version_string)
+        .unwrap_or_else(|| panic!("Unable to parse the OpenGL version from {:?}", version_string));
+    OPENGL_VERSION.with(|cached| cached.set(Some(version)));
+    version
+}
+
+/// Returns true if the current context advertises the named extension, e.g. "GL_ARB_get_program_binary"
+pub fn has_extension(name: &str) -> bool {
+    let mut count = 0;
+    unsafe{ gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count); }
+
+    (0..count as gl::types::GLuint).any(|index| {
+        let extension = unsafe{ gl::GetStringi(gl::EXTENSIONS, index) };
+        !extension.is_null() && unsafe{ std::ffi::CStr::from_ptr(extension as _) }.to_bytes() == name.as_bytes()
+    })
+}
+
+/// Implementation defined limits of the current context, queried once and cached
+#[derive(Debug, Clone, Copy)]
+pub struct GlLimits {
+    pub max_vertex_attribs: usize,
+    pub max_texture_image_units: usize,
+    pub max_combined_texture_image_units: usize,
+    pub max_texture_size: usize,
+    pub max_uniform_block_size: usize,
+    pub max_color_attachments: usize,
+    pub max_draw_buffers: usize,
+    pub max_samples: usize,
+    /// Zero when the context doesn't support the debug message log
+    pub max_debug_message_length: usize,
+}
+
+pub fn get_opengl_limits() -> GlLimits {
+    if let Some(limits) = OPENGL_LIMITS.with(Cell::get) {
+        return limits;
+    }
+
+    let get_integer = |parameter| {
+        let mut value = 0;
+        unsafe{ gl::GetIntegerv(parameter, &mut value); }
+        value.max(0) as usize
+    };
+
+    // Querying an unsupported parameter raises 'GL_INVALID_ENUM', so check support first
+    let max_debug_message_length =
+        if get_opengl_version().supports_debug_message_log() || has_extension("GL_KHR_debug") {
+            get_integer(gl::MAX_DEBUG_MESSAGE_LENGTH)
+        } else {
+            0
+        };
+
+    let limits = GlLimits {
+        max_vertex_attribs: get_integer(gl::MAX_VERTEX_ATTRIBS),
+        max_texture_image_units: get_integer(gl::MAX_TEXTURE_IMAGE_UNITS),
+        max_combined_texture_image_units: get_integer(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS),
+        max_texture_size: get_integer(gl::MAX_TEXTURE_SIZE),
+        max_uniform_block_size: get_integer(gl::MAX_UNIFORM_BLOCK_SIZE),
+        max_color_attachments: get_integer(gl::MAX_COLOR_ATTACHMENTS),
+        max_draw_buffers: get_integer(gl::MAX_DRAW_BUFFERS),
+        max_samples: get_integer(gl::MAX_SAMPLES),
+        max_debug_message_length,
+    };
+    OPENGL_LIMITS.with(|cached| cached.set(Some(limits)));
+    limits
+}
+
+/// The sample counts a renderbuffer of 'internal_format' (e.g. 'GL_RGBA8') supports for MSAA,
+/// highest first - 'glGetInternalformativ' with 'GL_SAMPLES'
+///
+/// Choose from these before allocating multisampled storage, as an unsupported count leaves the
+/// framebuffer incomplete. The query requires OpenGL 4.2 or 'GL_ARB_internalformat_query',
+/// otherwise this assumes every power of two up to 'GL_MAX_SAMPLES', which most formats support.
+pub fn supported_samples(internal_format: gl::types::GLenum) -> Vec<i32> {
+    let version = get_opengl_version();
+    if !version.at_least(4, 2) && !has_extension("GL_ARB_internalformat_query") {
+        return fallback_sample_counts(get_opengl_limits().max_samples);
+    }
+
+    let mut count = 0;
+    unsafe{ gl::GetInternalformativ(gl::RENDERBUFFER, internal_format, gl::NUM_SAMPLE_COUNTS, 1, &mut count); }
+
+    let mut samples = vec![0; count.max(0) as usize];
+    if !samples.is_empty() {
         unsafe{
-            OPENGL_VERSION = Some(OpenGLVersion{
-                major: (verion_bytes[0] - 48) as usize,
-                minor: (verion_bytes[2] - 48) as usize
-            });
+            gl::GetInternalformativ(gl::RENDERBUFFER, internal_format, gl::SAMPLES, samples.len() as _, samples.as_mut_ptr());
         }
     }
+    samples
+}
 
-    if let Some(version) = unsafe{ OPENGL_VERSION } {
-        version
-    } else {
-        unreachable!()
-    }
+/// Powers of two from 'max_samples' down to 2, highest first
+pub(crate) fn fallback_sample_counts(max_samples: usize) -> Vec<i32> {
+    let mut samples: Vec<i32> = std::iter::successors(Some(2i32), |samples| samples.checked_mul(2))
+        .take_while(|&samples| samples as usize <= max_samples)
+        .collect();
+    samples.reverse();
+    samples
 }
 
 pub fn get_opengl_vendor_string() -> String { get_opengl_string(OpenGLStringId::Vendor) }
@@ -0,0 +1,14 @@
+// A separate test binary, as loading null function pointers affects the whole process
+
+#[test]
+fn init_without_context_fails() {
+    let mut requested = Vec::new();
+    let result = avocet::init(|symbol| {
+        requested.push(symbol.to_owned());
+        std::ptr::null()
+    });
+
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    assert!(requested.iter().any(|symbol| symbol == "glGetString"));
+    assert!(!avocet::validation::is_context_current());
+}
\ No newline at end of file
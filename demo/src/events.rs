@@ -0,0 +1,131 @@
+use glfw::{Action, GlfwReceiver, WindowEvent};
+
+/// A key, independent of GLFW's key codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// 'A' to 'Z', always upper case - use 'Event::Char' for text input
+    Letter(char),
+    /// '0' to '9' on the main keyboard
+    Digit(u8),
+    /// F1 to F25
+    Function(u8),
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Space,
+    Left,
+    Right,
+    Up,
+    Down,
+    /// Any other key, as its GLFW key code
+    Other(i32),
+}
+
+impl From<glfw::Key> for Key {
+    fn from(key: glfw::Key) -> Self {
+        let code = key as i32;
+        match key {
+            glfw::Key::Escape => Key::Escape,
+            glfw::Key::Enter => Key::Enter,
+            glfw::Key::Tab => Key::Tab,
+            glfw::Key::Backspace => Key::Backspace,
+            glfw::Key::Space => Key::Space,
+            glfw::Key::Left => Key::Left,
+            glfw::Key::Right => Key::Right,
+            glfw::Key::Up => Key::Up,
+            glfw::Key::Down => Key::Down,
+            _ if (glfw::Key::A as i32..=glfw::Key::Z as i32).contains(&code) => Key::Letter(char::from(code as u8)),
+            _ if (glfw::Key::Num0 as i32..=glfw::Key::Num9 as i32).contains(&code) => Key::Digit((code - glfw::Key::Num0 as i32) as u8),
+            _ if (glfw::Key::F1 as i32..=glfw::Key::F25 as i32).contains(&code) => Key::Function((code - glfw::Key::F1 as i32 + 1) as u8),
+            _ => Key::Other(code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    /// Any other button, numbered from 0 as in GLFW
+    Other(u8),
+}
+
+impl From<glfw::MouseButton> for MouseButton {
+    fn from(button: glfw::MouseButton) -> Self {
+        match button {
+            glfw::MouseButtonLeft => MouseButton::Left,
+            glfw::MouseButtonRight => MouseButton::Right,
+            glfw::MouseButtonMiddle => MouseButton::Middle,
+            _ => MouseButton::Other(button as u8),
+        }
+    }
+}
+
+/// The window events the demo responds to, decoupled from GLFW's 'WindowEvent'
+///
+/// Only events whose polling is enabled on the window are delivered, e.g. 'set_key_polling'
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The framebuffer's new size in pixels, which is what the viewport expects
+    FramebufferResized { width: i32, height: i32 },
+    /// Key repeats aren't reported, only the initial press
+    KeyPressed(Key),
+    KeyReleased(Key),
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
+    /// The cursor's position in screen coordinates, relative to the window's top-left corner
+    CursorMoved { x: f64, y: f64 },
+    Scrolled { x: f64, y: f64 },
+    /// A character was typed, after keyboard layout and modifiers have been applied
+    Char(char),
+    Focused(bool),
+    CloseRequested,
+}
+
+impl Event {
+    /// 'None' for events with no equivalent, and for key repeats
+    fn from_glfw(event: WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::FramebufferSize(width, height) => Some(Event::FramebufferResized { width, height }),
+            WindowEvent::Key(key, _, Action::Press, _) => Some(Event::KeyPressed(key.into())),
+            WindowEvent::Key(key, _, Action::Release, _) => Some(Event::KeyReleased(key.into())),
+            WindowEvent::MouseButton(button, Action::Press, _) => Some(Event::MouseButtonPressed(button.into())),
+            WindowEvent::MouseButton(button, Action::Release, _) => Some(Event::MouseButtonReleased(button.into())),
+            WindowEvent::CursorPos(x, y) => Some(Event::CursorMoved { x, y }),
+            WindowEvent::Scroll(x, y) => Some(Event::Scrolled { x, y }),
+            WindowEvent::Char(character) => Some(Event::Char(character)),
+            WindowEvent::Focus(focused) => Some(Event::Focused(focused)),
+            WindowEvent::Close => Some(Event::CloseRequested),
+            _ => None,
+        }
+    }
+}
+
+/// An event along with when it happened, in seconds on the same clock as 'WindowManager::time'
+///
+/// The time is when GLFW received the event rather than when it's handled, so intervals
+/// between events are precise regardless of the frame rate, e.g. for detecting double clicks
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedEvent {
+    pub time: f64,
+    pub event: Event,
+}
+
+/// The events of a single window, as returned alongside it by 'WindowManager::create_window'
+pub struct EventQueue {
+    receiver: GlfwReceiver<(f64, WindowEvent)>,
+}
+
+impl EventQueue {
+    pub fn new(receiver: GlfwReceiver<(f64, WindowEvent)>) -> Self {
+        Self { receiver }
+    }
+
+    /// Drains the events received by the last 'WindowManager::poll_events', oldest first
+    pub fn events(&self) -> impl Iterator<Item = TimedEvent> + '_ {
+        glfw::flush_messages(&self.receiver)
+            .filter_map(|(time, event)| Event::from_glfw(event).map(|event| TimedEvent { time, event }))
+    }
+}
\ No newline at end of file
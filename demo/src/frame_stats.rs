@@ -0,0 +1,64 @@
+use std::{collections::VecDeque, time::Duration};
+use glfw::PWindow;
+
+/// Rolling frame time statistics over the last 'capacity' frames
+pub struct FrameStats {
+    frame_times: VecDeque<Duration>,
+    capacity: usize,
+    frame_count: u64,
+}
+
+impl FrameStats {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "FrameStats needs room for at least one frame");
+        Self { frame_times: VecDeque::with_capacity(capacity), capacity, frame_count: 0 }
+    }
+
+    /// Records the duration of a frame, discarding the oldest once full
+    pub fn record(&mut self, delta: Duration) {
+        if self.frame_times.len() == self.capacity {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta);
+        self.frame_count += 1;
+    }
+
+    /// The total number of frames recorded, including those no longer in the window
+    #[allow(dead_code)]
+    pub fn frame_count(&self) -> u64 { self.frame_count }
+
+    /// The mean frame time over the recorded frames
+    pub fn average_frame_time(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+    }
+
+    /// Frames per second based on the average frame time, or 0 before any frames are recorded
+    pub fn fps(&self) -> f32 {
+        let average = self.average_frame_time().as_secs_f32();
+        if average > 0.0 { 1.0 / average } else { 0.0 }
+    }
+
+    pub fn min_frame_time(&self) -> Duration {
+        self.frame_times.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max_frame_time(&self) -> Duration {
+        self.frame_times.iter().max().copied().unwrap_or_default()
+    }
+
+    /// Shows the statistics after 'title' in the window's title bar
+    pub fn update_title(&self, window: &mut PWindow, title: &str) {
+        window.set_title(&format!(
+            "{} - {:.0} FPS ({:.2}ms, min {:.2}ms, max {:.2}ms)",
+            title,
+            self.fps(),
+            self.average_frame_time().as_secs_f64() * 1000.0,
+            self.min_frame_time().as_secs_f64() * 1000.0,
+            self.max_frame_time().as_secs_f64() * 1000.0,
+        ));
+    }
+}
\ No newline at end of file
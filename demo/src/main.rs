@@ -1,16 +1,22 @@
+mod events;
+mod frame_stats;
+mod renderer;
 mod util;
 
 #[cfg(test)]
 mod tests;
 
-use std::path::PathBuf;
-use glfw::{self, Context};
+use std::{path::PathBuf, time::Duration};
 
 use avocet::{
     graphics as ag,
     geometry::Triangle,
+    validation::{self, ValidationMode},
 };
 
+use events::{Event, EventQueue, Key};
+use frame_stats::FrameStats;
+use renderer::Renderer;
 use util::{WindowConfig, WindowManager};
 
 fn get_shader_path(filename: &str) -> PathBuf {
@@ -31,7 +37,29 @@ fn get_shader_path(filename: &str) -> PathBuf {
     path
 }
 
+const WINDOW_TITLE: &str = "Hello Rendering Engine";
+/// Seconds between refreshes of the frame rate shown in the title
+const TITLE_UPDATE_INTERVAL: f64 = 0.5;
+
+/// F2 toggles vsync, uncapping the frame rate shown in the title while benchmarking
+fn toggle_vsync(window_manager: &mut WindowManager, vsync: &mut bool) {
+    *vsync = !*vsync;
+    window_manager.set_swap_interval(if *vsync { glfw::SwapInterval::Sync(1) } else { glfw::SwapInterval::None });
+    println!("Vsync: {}", if *vsync { "on" } else { "off" });
+}
+
+/// F1 cycles through these, so heavy checking can be enabled only while something looks wrong
+fn next_validation_mode(mode: ValidationMode) -> ValidationMode {
+    match mode {
+        ValidationMode::None => ValidationMode::Basic,
+        ValidationMode::Basic => ValidationMode::Advanced,
+        ValidationMode::Advanced | ValidationMode::Dynamic => ValidationMode::None,
+    }
+}
+
 fn main() {
+    util::initialise_logging(log::LevelFilter::Debug);
+
     let mut window_manager = match WindowManager::new() {
         Ok(wm) => wm,
         Err(init_error) => {
@@ -40,12 +68,14 @@ fn main() {
         },
     };
 
-    let (mut window, _receiver) = window_manager.create_window(WindowConfig{
+    let (mut window, receiver) = window_manager.create_window(WindowConfig{
         width: 800,
         height: 600,
-        title: "Hello Rendering Engine",
-        visible: true,
+        title: WINDOW_TITLE,
+        samples: 4,
+        ..WindowConfig::default()
     }).expect("Failed to create GLFW window");
+    let event_queue = EventQueue::new(receiver);
 
     println!(
         "Vendor: {}\nRenderer: {}\nVersion: {}",
@@ -54,23 +84,75 @@ fn main() {
         avocet::version::get_opengl_version_string(),
     );
 
+    println!(
+        "Context: OpenGL {} ({} build on {}, {} validation)",
+        window_manager.version(),
+        avocet::config::build_mode(),
+        avocet::config::target_platform(),
+        avocet::validation::validation_mode(),
+    );
+
+    window.set_key_polling(true);
+    ag::set_multisample(true);
+    println!("Samples: {}", ag::sample_count());
+
+    for (index, monitor) in window_manager.available_monitors().iter().enumerate() {
+        println!(
+            "Monitor {}: {} ({}x{} @ {}Hz at {:?}){}",
+            index,
+            monitor.name,
+            monitor.width,
+            monitor.height,
+            monitor.refresh_rate,
+            monitor.position,
+            if monitor.is_primary { " - primary" } else { "" },
+        );
+    }
+
     // Build and compile shaders
     let vertex_path = get_shader_path("identity_vert.glsl");
     let fragment_path = get_shader_path("monochrome_frag.glsl");
     let shader_program = ag::ShaderProgram::new(vertex_path, fragment_path).unwrap();
     let triangle = Triangle::new();
 
+    let mut renderer = Renderer::new();
+    renderer.set_clear_color(ag::Color::rgb(0.2, 0.3, 0.3));
+    renderer.add(move || {
+        shader_program.bind();
+        triangle.draw();
+    });
+
+    let mut frame_stats = FrameStats::new(120);
+    let mut last_frame = window_manager.time();
+    let mut last_title_update = last_frame;
+    let mut vsync = true;
+
     // The core program loop
     while !window.should_close() {
-        unsafe{
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+        let now = window_manager.time();
+        frame_stats.record(Duration::from_secs_f64(now - last_frame));
+        last_frame = now;
 
-            shader_program.bind();
-            triangle.draw();
-        };
+        // Refresh the title a couple of times a second rather than every frame
+        if now - last_title_update >= TITLE_UPDATE_INTERVAL {
+            frame_stats.update_title(&mut window, WINDOW_TITLE);
+            last_title_update = now;
+        }
 
-        window.swap_buffers(); // 'glfwSwapBuffers'
+        renderer.render_frame(&mut window);
         window_manager.poll_events(); // 'glfwPollEvents'
+
+        for timed_event in event_queue.events() {
+            match timed_event.event {
+                Event::FramebufferResized { width, height } => ag::set_viewport(0, 0, width, height),
+                Event::KeyPressed(Key::Function(1)) => {
+                    let mode = next_validation_mode(validation::active_validation_mode());
+                    validation::set_validation_mode(mode);
+                    println!("Validation: {}", validation::active_validation_mode());
+                },
+                Event::KeyPressed(Key::Function(2)) => toggle_vsync(&mut window_manager, &mut vsync),
+                _ => {},
+            }
+        }
     }
 }
\ No newline at end of file
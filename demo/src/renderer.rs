@@ -0,0 +1,41 @@
+use glfw::{Context, PWindow};
+use avocet::{graphics as ag, geometry::Drawable};
+
+/// Draws a list of drawables each frame: clear, draw each in the order added, then present
+///
+/// Geometry can be added directly, drawn with whichever program is bound, or as a closure
+/// binding its own program, e.g. 'renderer.add(move || { program.bind(); triangle.draw(); })'
+pub struct Renderer {
+    clear_color: ag::Color,
+    drawables: Vec<Box<dyn Drawable>>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self { clear_color: ag::Color::BLACK, drawables: Vec::new() }
+    }
+
+    pub fn set_clear_color(&mut self, color: ag::Color) {
+        self.clear_color = color;
+    }
+
+    pub fn add<D: Drawable + 'static>(&mut self, drawable: D) {
+        self.drawables.push(Box::new(drawable));
+    }
+
+    /// Clears every buffer, draws everything then swaps the window's buffers
+    pub fn render_frame(&self, window: &mut PWindow) {
+        ag::clear(&ag::ClearState::all(self.clear_color));
+
+        for drawable in &self.drawables {
+            drawable.draw();
+        }
+        ag::reset_state();
+
+        window.swap_buffers(); // 'glfwSwapBuffers'
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self { Self::new() }
+}
\ No newline at end of file
@@ -1,8 +1,12 @@
-use std::path::PathBuf;
-use glfw::PWindow;
-use avocet::graphics as ag;
+use std::{path::PathBuf, time::Duration};
+use glfw::{Context, PWindow};
+use avocet::{graphics as ag, geometry::{Drawable, FullscreenQuad, Mesh, Triangle, VertexLayout}};
 
-use crate::util::{self, WindowManager};
+use crate::{
+    events::{Key, MouseButton},
+    frame_stats::FrameStats,
+    util::{self, ContextGuard, WindowManager},
+};
 
 fn get_test_asset_path(filename: &str) -> PathBuf {
     const CARGO_MANIFEST_DIR: &'static str = std::env!("CARGO_MANIFEST_DIR");
@@ -22,21 +26,81 @@ fn get_test_asset_path(filename: &str) -> PathBuf {
     path
 }
 
-fn setup() -> (WindowManager, PWindow) {
-    let mut manager = util::WindowManager::new().unwrap();
-    let (window, _) = manager.create_window(util::WindowConfig::hidden()).unwrap();
-    (manager, window)
+/// Creates a window and its context, or returns 'None' when no context can be created
+/// (e.g. a headless CI machine) so tests needing one can be skipped rather than failing
+///
+/// GLFW is initialised with 'fail_on_errors', which panics on errors, hence 'catch_unwind'
+fn try_setup(config: util::WindowConfig) -> Option<(WindowManager, PWindow)> {
+    util::initialise_logging(log::LevelFilter::Debug);
+
+    // Without a display GLFW fails to initialise, so don't try
+    if !util::display_available() {
+        return None;
+    }
+
+    std::panic::catch_unwind(move || {
+        let mut manager = util::WindowManager::new().ok()?;
+        let (window, _) = manager.create_window(config)?;
+        Some((manager, window))
+    }).ok().flatten()
+}
+
+/// Sets up a context with 'try_setup', or skips the calling test by returning early
+///
+/// Tests run on threads named after them, which names the test in the message
+macro_rules! require_context {
+    ($config:expr) => {
+        match try_setup($config) {
+            Some(setup) => setup,
+            None => {
+                let thread = std::thread::current();
+                eprintln!("Skipping '{}': unable to create an OpenGL context", thread.name().unwrap_or("test"));
+                return;
+            },
+        }
+    };
+}
+
+/// Asserts each channel is within 1 of the expected value, allowing for rounding differences between drivers
+fn assert_pixel_eq(actual: &[u8], expected: [u8; 4]) {
+    let matches = actual.iter().zip(expected).all(|(&actual, expected)| actual.abs_diff(expected) <= 1);
+    assert!(matches, "Expected pixel {:?}, found {:?}", expected, actual);
 }
 
 #[test]
 fn shader_program() {
-    let (_managerm, _window) = setup();
+    let (_manager, _window) = require_context!(util::WindowConfig::hidden());
 
     missing_vertex_shader();
     missing_fragment_shader();
     
     broken_vertex_shader();
     broken_fragment_shader();
+
+    program_from_stages();
+    program_with_version_header();
+}
+
+fn program_with_version_header() {
+    let vertex_source = "layout (location = 0) in vec3 position;\nvoid main() { gl_Position = vec4(position, 1.0); }";
+    let fragment_source = "out vec4 colour;\nvoid main() { colour = vec4(1.0); }";
+
+    assert!(ag::ShaderProgram::from_source_with_version(vertex_source, fragment_source, None).is_ok());
+    assert!(ag::ShaderProgram::from_source_with_version(vertex_source, fragment_source, Some("330 core")).is_ok());
+}
+
+fn program_from_stages() {
+    let vertex_path = get_test_asset_path("identity_vert.glsl");
+    let fragment_path = get_test_asset_path("monochrome_frag.glsl");
+
+    let stages = [(ag::ShaderStage::Vertex, &vertex_path), (ag::ShaderStage::Fragment, &fragment_path)];
+    let program = ag::ShaderProgram::from_stages(&stages).unwrap();
+    program.bind();
+    assert_eq!(program.raw_handle(), ag::current_program());
+
+    let duplicated = [(ag::ShaderStage::Vertex, &vertex_path), (ag::ShaderStage::Vertex, &vertex_path)];
+    let result = ag::ShaderProgram::from_stages(&duplicated);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
 }
 
 fn missing_vertex_shader() {
@@ -77,4 +141,451 @@ fn broken_fragment_shader() {
 
     assert!(result.is_err());
     assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn program_binary_round_trips() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, _window) = require_context!(config);
+
+    let program = FullscreenQuad::program("#version 330 core
+out vec4 colour;
+void main() { colour = vec4(0.0, 1.0, 0.0, 1.0); }
+").unwrap();
+
+    let binary_path = std::env::temp_dir().join(format!("avocet_program_binary_{}.bin", std::process::id()));
+    match program.save_binary(&binary_path) {
+        Err(error) if error.kind() == std::io::ErrorKind::Unsupported => {
+            eprintln!("Skipping 'program_binary_round_trips': program binaries aren't supported");
+            return;
+        },
+        result => result.unwrap(),
+    }
+
+    // Missing sources mean the fallback would fail, so this only succeeds by loading the binary
+    let missing_path = get_test_asset_path("missing_file.glsl");
+    let loaded = ag::ShaderProgram::from_binary(&binary_path, &missing_path, &missing_path);
+    std::fs::remove_file(&binary_path).unwrap();
+    let loaded = loaded.unwrap();
+
+    loaded.bind();
+    FullscreenQuad::new().draw();
+    assert_pixel_eq(&ag::read_pixels(32, 32, 1, 1), [0, 255, 0, 255]);
+}
+
+#[test]
+fn uniforms_read_back_set_values() {
+    let (_manager, _window) = require_context!(util::WindowConfig::hidden());
+
+    let program = FullscreenQuad::program("#version 330 core
+uniform vec4 tint;
+uniform float brightness;
+out vec4 colour;
+void main() { colour = tint * brightness; }
+").unwrap();
+
+    program.bind();
+    program.set_uniform_vec4("tint", [0.25, 0.5, 0.75, 1.0]);
+    program.set_uniform_f32("brightness", 2.0);
+
+    assert_eq!(program.get_uniform_vec4("tint"), Some([0.25, 0.5, 0.75, 1.0]));
+    assert_eq!(program.get_uniform_f32("brightness"), Some(2.0));
+    assert_eq!(program.get_uniform_f32("missing"), None);
+
+    let brightness = program.uniform_location("brightness");
+    assert!(brightness.is_active());
+    program.set_f32_at(brightness, 0.5);
+    assert_eq!(program.get_uniform_f32("brightness"), Some(0.5));
+
+    // Setting an inactive uniform is skipped, as with the named setters
+    let missing = program.uniform_location("missing");
+    assert!(!missing.is_active());
+    program.set_f32_at(missing, 1.0);
+}
+
+#[test]
+fn frame_stats_rolling_window() {
+    let mut stats = FrameStats::new(3);
+    assert_eq!(stats.fps(), 0.0);
+
+    for milliseconds in [40, 10, 20, 30] {
+        stats.record(Duration::from_millis(milliseconds));
+    }
+
+    // The first frame has been pushed out of the window
+    assert_eq!(stats.frame_count(), 4);
+    assert_eq!(stats.average_frame_time(), Duration::from_millis(20));
+    assert_eq!(stats.min_frame_time(), Duration::from_millis(10));
+    assert_eq!(stats.max_frame_time(), Duration::from_millis(30));
+    assert!((stats.fps() - 50.0).abs() < 0.01);
+}
+
+#[test]
+fn glfw_input_maps_to_events() {
+    assert_eq!(Key::from(glfw::Key::F1), Key::Function(1));
+    assert_eq!(Key::from(glfw::Key::F12), Key::Function(12));
+    assert_eq!(Key::from(glfw::Key::Q), Key::Letter('Q'));
+    assert_eq!(Key::from(glfw::Key::Num7), Key::Digit(7));
+    assert_eq!(Key::from(glfw::Key::Escape), Key::Escape);
+    assert_eq!(Key::from(glfw::Key::Home), Key::Other(glfw::Key::Home as i32));
+
+    assert_eq!(MouseButton::from(glfw::MouseButtonRight), MouseButton::Right);
+    assert_eq!(MouseButton::from(glfw::MouseButton::Button5), MouseButton::Other(4));
+}
+
+#[test]
+fn clear_color_reads_back() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, _window) = require_context!(config);
+
+    let expected = ag::Color::rgb(0.2, 0.4, 0.6);
+    let actual = ag::clear_and_read(expected, 0, 0);
+
+    let within_tolerance = actual.to_array().iter().zip(expected.to_array())
+        .all(|(actual, expected)| (actual - expected).abs() <= 1.0 / 255.0);
+    assert!(within_tolerance, "Expected {:?}, found {:?}", expected, actual);
+}
+
+#[test]
+fn screenshot_round_trips_through_png() {
+    let config = util::WindowConfig { width: 8, height: 8, ..util::WindowConfig::hidden() };
+    let (_manager, _window) = require_context!(config);
+
+    ag::clear(&ag::ClearState::color(ag::Color::rgb(1.0, 0.0, 0.0)));
+
+    let path = std::env::temp_dir().join(format!("avocet_screenshot_{}.png", std::process::id()));
+    ag::save_screenshot(&path, 8, 8).unwrap();
+    let image = ag::decode_image(&std::fs::read(&path).unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    let image = image.unwrap();
+    assert_eq!((image.width, image.height, image.format), (8, 8, ag::TextureFormat::RGBA8));
+    assert_pixel_eq(&image.data[..4], [255, 0, 0, 255]);
+}
+
+#[test]
+fn triangle_renders() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, window) = require_context!(config);
+
+    let vertex_path = get_test_asset_path("identity_vert.glsl");
+    let fragment_path = get_test_asset_path("monochrome_frag.glsl");
+    let shader_program = ag::ShaderProgram::new(vertex_path, fragment_path).unwrap();
+    assert!(shader_program.is_valid());
+    let triangle = Triangle::new();
+
+    ag::clear(&ag::ClearState::color(ag::Color::rgb(0.2, 0.3, 0.3)));
+    shader_program.bind();
+    triangle.draw();
+
+    // The triangle covers the centre of the framebuffer but not its corners
+    let (width, height) = window.get_framebuffer_size();
+    let centre = ag::read_pixels(width / 2, height / 2, 1, 1);
+    let corner = ag::read_pixels(0, 0, 1, 1);
+
+    // 'monochrome_frag.glsl' outputs (1.0, 0.5, 0.2, 1.0)
+    assert_pixel_eq(&centre, [255, 128, 51, 255]);
+    assert_pixel_eq(&corner, [51, 77, 77, 255]);
+}
+
+/// Fields drop in declaration order, so a 'Triangle' deletes its vertex array before the
+/// vertex buffer the array still references. That's valid: the buffer's name is only freed
+/// once nothing refers to it, so neither deletion should raise an error.
+#[test]
+fn triangle_drops_without_errors() {
+    let (_manager, _window) = require_context!(util::WindowConfig::hidden());
+
+    // Validation would panic during the drop, but check explicitly in case it's disabled
+    let triangle = Triangle::new();
+    triangle.draw();
+    drop(triangle);
+
+    assert_eq!(unsafe{ gl::GetError() }, gl::NO_ERROR);
+}
+
+#[test]
+fn fullscreen_quad_covers_viewport() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, window) = require_context!(config);
+
+    let program = FullscreenQuad::program("#version 330 core
+in vec2 uv;
+out vec4 colour;
+void main() { colour = vec4(1.0, 1.0, 1.0, 1.0); }
+").unwrap();
+    let quad = FullscreenQuad::new();
+
+    ag::clear(&ag::ClearState::color(ag::Color::BLACK));
+    program.bind();
+    quad.draw();
+
+    let (width, height) = window.get_framebuffer_size();
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [255, 255, 255, 255]);
+    assert_pixel_eq(&ag::read_pixels(width - 1, height - 1, 1, 1), [255, 255, 255, 255]);
+}
+
+#[test]
+fn mesh_layout_feeds_attributes() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, window) = require_context!(config);
+
+    let program = ag::ShaderProgram::from_source("#version 330 core
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec3 color;
+out vec3 vertex_color;
+void main() { vertex_color = color; gl_Position = vec4(position, 1.0); }
+", "#version 330 core
+in vec3 vertex_color;
+out vec4 colour;
+void main() { colour = vec4(vertex_color, 1.0); }
+").unwrap();
+
+    // A triangle covering the viewport, coloured red
+    let red = [1.0, 0.0, 0.0];
+    let vertices = [[[-1.0, -1.0, 0.0], red], [[3.0, -1.0, 0.0], red], [[-1.0, 3.0, 0.0], red]];
+    let mesh = Mesh::from_vertices(&vertices, &VertexLayout::new().attribute(0, 3).attribute(1, 3)).unwrap();
+
+    ag::clear(&ag::ClearState::color(ag::Color::BLACK));
+    program.bind();
+    mesh.draw();
+
+    let (width, height) = window.get_framebuffer_size();
+    assert_pixel_eq(&ag::read_pixels(width / 2, height / 2, 1, 1), [255, 0, 0, 255]);
+}
+
+#[test]
+fn sampler_overrides_texture_filter() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, _window) = require_context!(config);
+
+    // A quarter of the way between the centres of a black and a white texel
+    let program = FullscreenQuad::program("#version 330 core
+uniform sampler2D image;
+out vec4 colour;
+void main() { colour = texture(image, vec2(0.375, 0.5)); }
+").unwrap();
+    let quad = FullscreenQuad::new();
+    let texture = ag::Texture2D::new(2, 1, &[0, 0, 0, 255, 255, 255, 255, 255], false);
+    let sampler = ag::Sampler::new().unwrap();
+    sampler.set_filter(ag::TextureFilter::Nearest, ag::TextureFilter::Nearest);
+
+    program.bind();
+    texture.bind(0);
+    quad.draw();
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [64, 64, 64, 255]);
+
+    sampler.bind(0);
+    quad.draw();
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [0, 0, 0, 255]);
+
+    ag::Sampler::unbind(0);
+    quad.draw();
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [64, 64, 64, 255]);
+}
+
+#[test]
+fn volume_textures_wrap_every_coordinate() {
+    let (_manager, _window) = require_context!(util::WindowConfig::hidden());
+
+    let texture = ag::Texture3D::new(1, 1, 1, &[255, 255, 255, 255], false);
+    texture.set_filter(ag::TextureFilter::Nearest, ag::TextureFilter::Nearest);
+    texture.set_wrap(ag::TextureWrap::ClampToEdge);
+
+    let parameter = |name| {
+        let mut value = 0;
+        unsafe{ gl::GetTexParameteriv(gl::TEXTURE_3D, name, &mut value) };
+        value as gl::types::GLenum
+    };
+    assert_eq!(parameter(gl::TEXTURE_MIN_FILTER), gl::NEAREST);
+    for coordinate in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+        assert_eq!(parameter(coordinate), gl::CLAMP_TO_EDGE);
+    }
+}
+
+#[test]
+fn drawables_of_different_types_draw_from_one_list() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, _window) = require_context!(config);
+
+    let program = FullscreenQuad::program("#version 330 core
+out vec4 colour;
+void main() { colour = vec4(1.0, 1.0, 1.0, 1.0); }
+").unwrap();
+
+    let drawables: Vec<Box<dyn Drawable>> = vec![
+        Box::new(Triangle::new()),
+        Box::new(FullscreenQuad::new()),
+        Box::new(|| ag::clear(&ag::ClearState::color(ag::Color::BLACK))),
+    ];
+
+    // Only the closure clears, so everything drawn before it is overwritten
+    ag::clear(&ag::ClearState::color(ag::Color::WHITE));
+    program.bind();
+    for drawable in &drawables {
+        drawable.draw();
+    }
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [0, 0, 0, 255]);
+
+    drawables[1].draw();
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [255, 255, 255, 255]);
+}
+
+#[test]
+fn framebuffer_clears_each_attachment() {
+    let (_manager, _window) = require_context!(util::WindowConfig::hidden());
+
+    let pixels = vec![0; 4 * 4 * 4];
+    let albedo = ag::Texture2D::new(4, 4, &pixels, false);
+    let normals = ag::Texture2D::new(4, 4, &pixels, false);
+
+    let framebuffer = ag::Framebuffer::new();
+    framebuffer.attach_color(0, &albedo).unwrap();
+    framebuffer.attach_color(1, &normals).unwrap();
+    framebuffer.set_draw_buffers(&[0, 1]).unwrap();
+    assert!(framebuffer.is_complete());
+
+    framebuffer.clear_attachment(0, ag::Color::new(1.0, 0.0, 0.0, 1.0)).unwrap();
+    framebuffer.clear_attachment(1, ag::Color::new(0.0, 0.0, 1.0, 1.0)).unwrap();
+
+    framebuffer.set_read_buffer(0).unwrap();
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [255, 0, 0, 255]);
+    framebuffer.set_read_buffer(1).unwrap();
+    assert_pixel_eq(&ag::read_pixels(0, 0, 1, 1), [0, 0, 255, 255]);
+
+    // Attachment 2 has no texture
+    assert!(framebuffer.set_read_buffer(2).is_err());
+    ag::Framebuffer::bind_default();
+}
+
+#[test]
+fn primary_monitor_is_listed_first() {
+    let (mut manager, _window) = require_context!(util::WindowConfig::hidden());
+
+    let monitors = manager.available_monitors();
+
+    // Headless machines may have no monitors at all
+    if let Some(first) = monitors.first() {
+        assert!(first.is_primary);
+        assert_eq!(monitors.iter().filter(|monitor| monitor.is_primary).count(), 1);
+        if let Some(mode) = manager.primary_video_mode() {
+            assert_eq!((first.width, first.height, first.refresh_rate), mode);
+        }
+    } else {
+        assert_eq!(manager.primary_video_mode(), None);
+    }
+}
+
+#[test]
+fn multisample_framebuffer_resolves() {
+    let (_manager, _window) = require_context!(util::WindowConfig::hidden());
+
+    // Every implementation supports at least 4 samples of the common formats
+    assert!(avocet::version::supported_samples(gl::RGBA8).contains(&4));
+    assert!(avocet::version::supported_samples(gl::DEPTH24_STENCIL8).contains(&4));
+
+    let multisampled = ag::Texture2DMultisample::new(4, 4, 4, false).unwrap();
+    let depth = ag::Renderbuffer::new();
+    depth.storage_multisample(ag::RenderbufferFormat::Depth24Stencil8, 4, 4, 4).unwrap();
+
+    let msaa_framebuffer = ag::Framebuffer::new();
+    msaa_framebuffer.attach_color_multisample(0, &multisampled).unwrap();
+    msaa_framebuffer.attach_depth(&depth);
+    assert!(msaa_framebuffer.is_complete());
+
+    let resolved = ag::Texture2D::new(4, 4, &[0; 4 * 4 * 4], false);
+    let resolve_framebuffer = ag::Framebuffer::new();
+    resolve_framebuffer.attach_color(0, &resolved).unwrap();
+    assert!(resolve_framebuffer.is_complete());
+
+    msaa_framebuffer.clear_attachment(0, ag::Color::new(0.0, 1.0, 0.0, 1.0)).unwrap();
+    msaa_framebuffer.blit_to(Some(&resolve_framebuffer), 4, 4);
+
+    resolve_framebuffer.bind();
+    assert_pixel_eq(&ag::read_pixels(3, 3, 1, 1), [0, 255, 0, 255]);
+
+    assert!(ag::Texture2DMultisample::new(4, 4, 0, false).is_err());
+    ag::Framebuffer::bind_default();
+}
+
+#[test]
+fn context_guard_restores_previous_context() {
+    let (mut manager, mut window) = require_context!(util::WindowConfig::hidden());
+
+    let (mut secondary, _) = manager.create_window(util::WindowConfig::hidden()).unwrap();
+    window.make_current();
+
+    {
+        let _guard = ContextGuard::new(&mut secondary);
+        assert!(secondary.is_current());
+    }
+
+    assert!(window.is_current());
+}
+
+#[test]
+fn read_pixels_ignores_pack_alignment() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, _window) = require_context!(config);
+
+    ag::clear(&ag::ClearState::color(ag::Color::WHITE));
+
+    // Rows of 3 RGBA pixels are 12 bytes, which would be padded to 16 with an alignment of 8
+    ag::set_pack_alignment(8);
+    let pixels = ag::read_pixels(0, 0, 3, 3);
+
+    assert_eq!(pixels.len(), 3 * 3 * 4);
+    for pixel in pixels.chunks_exact(4) {
+        assert_pixel_eq(pixel, [255, 255, 255, 255]);
+    }
+    assert_eq!(ag::pack_alignment(), 8);
+    ag::set_pack_alignment(4);
+}
+
+#[test]
+fn scissor_restricts_clear() {
+    let config = util::WindowConfig { width: 64, height: 64, ..util::WindowConfig::hidden() };
+    let (_manager, window) = require_context!(config);
+
+    let (width, height) = window.get_framebuffer_size();
+    ag::clear(&ag::ClearState::color(ag::Color::BLACK));
+
+    // Only the left half is cleared to white
+    ag::set_scissor_test(true);
+    ag::set_scissor(0, 0, width / 2, height);
+    ag::clear(&ag::ClearState::color(ag::Color::WHITE));
+    ag::set_scissor_test(false);
+
+    assert_pixel_eq(&ag::read_pixels(width / 4, height / 2, 1, 1), [255, 255, 255, 255]);
+    assert_pixel_eq(&ag::read_pixels(width * 3 / 4, height / 2, 1, 1), [0, 0, 0, 255]);
+
+    // Once disabled, the whole framebuffer is affected again
+    ag::clear(&ag::ClearState::color(ag::Color::WHITE));
+    assert_pixel_eq(&ag::read_pixels(width * 3 / 4, height / 2, 1, 1), [255, 255, 255, 255]);
+
+    // 'with_scissor' restores the disabled test afterwards
+    ag::with_scissor(0, 0, width / 2, height, || ag::clear(&ag::ClearState::color(ag::Color::BLACK)));
+    assert_pixel_eq(&ag::read_pixels(width / 4, height / 2, 1, 1), [0, 0, 0, 255]);
+    assert_pixel_eq(&ag::read_pixels(width * 3 / 4, height / 2, 1, 1), [255, 255, 255, 255]);
+
+    ag::clear(&ag::ClearState::color(ag::Color::BLACK));
+    assert_pixel_eq(&ag::read_pixels(width * 3 / 4, height / 2, 1, 1), [0, 0, 0, 255]);
+}
+
+#[test]
+fn unowned_wrappers_leave_objects_alive() {
+    let (_manager, _window) = require_context!(util::WindowConfig::hidden());
+
+    let texture = ag::Texture2D::new(1, 1, &[255, 0, 0, 255], false);
+    let program = FullscreenQuad::program("#version 330 core
+out vec4 colour;
+void main() { colour = vec4(1.0); }
+").unwrap();
+
+    // Dropping the wrappers must leave the objects to their owners
+    drop(unsafe{ ag::Texture2D::from_raw_unowned(texture.raw_handle().index(), 1, 1, texture.format()) });
+    drop(unsafe{ ag::ShaderProgram::from_raw_unowned(program.raw_handle().index()) });
+
+    assert_eq!(unsafe{ gl::IsTexture(texture.raw_handle().index()) }, gl::TRUE);
+    assert_eq!(unsafe{ gl::IsProgram(program.raw_handle().index()) }, gl::TRUE);
+    assert_eq!(unsafe{ gl::GetError() }, gl::NO_ERROR);
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
-use glfw::{ Context, Glfw, GlfwReceiver, OpenGlProfileHint, PWindow, WindowEvent, WindowHint };
-use avocet::{ version, validation::ValidationMode };
+use std::path::{Path, PathBuf};
+use glfw::{ Context, Glfw, GlfwReceiver, OpenGlProfileHint, PixelImage, PWindow, SwapInterval, WindowEvent, WindowHint };
+use avocet::{ graphics as ag, version, validation::ValidationMode };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InitError {
@@ -13,23 +14,154 @@ impl From<glfw::InitError> for InitError {
     }
 }
 
+/// The OpenGL profile requested for a window's context
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlProfile {
+    /// Only the modern, non-deprecated API
+    Core,
+    /// Includes the deprecated API (e.g. immediate mode) - note Mac only supports this up to OpenGL 2.1
+    Compat,
+    /// Lets the driver decide
+    Any,
+}
+
+impl From<GlProfile> for OpenGlProfileHint {
+    fn from(value: GlProfile) -> Self {
+        match value {
+            GlProfile::Core => OpenGlProfileHint::Core,
+            GlProfile::Compat => OpenGlProfileHint::Compat,
+            GlProfile::Any => OpenGlProfileHint::Any,
+        }
+    }
+}
+
+/// Note: 'width' and 'height' are the logical window size in screen coordinates.
+/// On HiDPI displays the framebuffer backing the window is larger than this
+/// (scaled by the monitor's content scale), see 'WindowManager::content_scale'.
 pub struct WindowConfig {
     pub width: u32,
     pub height: u32,
     pub title: &'static str,
     pub visible: bool,
+    /// When true (the default) the window and its framebuffer are scaled by the
+    /// monitor's content scale. Set to false for a 1:1 mapping of window size to pixels
+    pub scale_to_monitor: bool,
+    /// Path to an image, typically a PNG, to use as the window icon
+    pub icon: Option<PathBuf>,
+    /// Locks the window's aspect ratio as (numerator, denominator)
+    pub aspect_ratio: Option<(u32, u32)>,
+    pub profile: GlProfile,
+    /// Removes deprecated functionality from the context, required for core profiles on Mac
+    pub forward_compat: bool,
+    /// The number of samples per pixel requested for the default framebuffer (0 disables MSAA)
+    ///
+    /// This is only a request, the driver may clamp it - see 'graphics::sample_count' for the result
+    pub samples: u32,
+    /// Requests a context which reports GPU resets rather than hanging or crashing,
+    /// see 'validation::check_context_reset'
+    pub lose_context_on_reset: bool,
+    /// Creates the window fullscreen at the monitor's native resolution and refresh rate
+    /// (see 'WindowManager::available_monitors') - 'width' and 'height' are ignored
+    pub fullscreen: bool,
+    /// The index into 'WindowManager::available_monitors' of the monitor to go fullscreen on,
+    /// or 'None' for the primary monitor. Only applies to fullscreen windows
+    pub monitor: Option<usize>,
+    /// Pins the context to a specific OpenGL version, rather than the highest version found
+    /// by 'WindowManager::new' - useful to reproduce a bug or test a minimum requirement
+    pub context_version: Option<version::OpenGLVersion>,
+    /// Renders to a back buffer which 'swap_buffers' presents (the default). Single buffered
+    /// windows draw straight to the screen, so tearing is visible and 'swap_buffers' does nothing
+    pub double_buffer: bool,
+    /// How many vertical blanks 'swap_buffers' waits for: 'Sync(1)' (the default) is vsync,
+    /// 'None' presents immediately for the lowest latency and 'Adaptive' only waits when
+    /// the frame is on time. Only applies to double buffered windows
+    ///
+    /// Triple buffering isn't exposed by GLFW, it's controlled by the driver's settings
+    pub swap_interval: SwapInterval,
 }
 
 impl WindowConfig {
     #[allow(dead_code)]
     pub fn hidden() -> Self {
-        Self { width: 1, height: 1, title: "", visible: false }
+        Self { width: 1, height: 1, title: "", visible: false, ..Self::default() }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            title: "",
+            visible: true,
+            scale_to_monitor: true,
+            icon: None,
+            aspect_ratio: None,
+            profile: GlProfile::Core,
+            forward_compat: true,
+            samples: 0,
+            lose_context_on_reset: false,
+            fullscreen: false,
+            monitor: None,
+            context_version: None,
+            double_buffer: true,
+            swap_interval: SwapInterval::Sync(1),
+        }
+    }
+}
+
+/// Whether there's a display server to create windows on
+///
+/// Windows and Mac always have one, whereas on Linux GLFW needs an X11 or Wayland display, which
+/// headless machines (e.g. CI runners) lack - run under 'xvfb-run' to provide a virtual one
+#[cfg(test)]
+pub fn display_available() -> bool {
+    if !avocet::config::is_linux() {
+        return true;
+    }
+
+    ["DISPLAY", "WAYLAND_DISPLAY"].iter()
+        .any(|variable| std::env::var_os(variable).is_some_and(|value| !value.is_empty()))
+}
+
+/// A minimal logger which forwards avocet's log messages to stderr
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool { true }
+
+    fn log(&self, record: &log::Record) {
+        eprintln!("[{}] {}", record.level(), record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs the stderr logger - only the first call has any effect
+pub fn initialise_logging(level: log::LevelFilter) {
+    static LOGGER: StderrLogger = StderrLogger;
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
     }
 }
 
+/// A connected monitor and its current video mode, see 'WindowManager::available_monitors'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorInfo {
+    pub name: String,
+    /// The current resolution in pixels, zero if the mode couldn't be queried
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+    /// The monitor's top-left corner on the virtual desktop, in screen coordinates
+    pub position: (i32, i32),
+    pub is_primary: bool,
+}
+
 pub struct WindowManager {
     glfw: Glfw,
     version: version::OpenGLVersion,
+    functions_loaded: bool,
 }
 
 impl WindowManager {
@@ -40,37 +172,205 @@ impl WindowManager {
         };
 
         if let Some(version) = find_opengl_version(&mut glfw) {
-            Ok(Self { glfw, version })
+            // The functions loaded whilst probing the version belong to a destroyed context
+            Ok(Self { glfw, version, functions_loaded: false })
         } else {
             Err(InitError::RetrieveOpenGLVersion)
         }
     }
 
+    /// The OpenGL version negotiated when the manager was created, which all windows request
+    pub fn version(&self) -> version::OpenGLVersion { self.version }
+
     pub fn create_window(&mut self, config: WindowConfig) -> Option<(PWindow, GlfwReceiver<(f64, WindowEvent)>)> {
-        self.glfw.window_hint(WindowHint::ContextVersion(self.version.major as _, self.version.minor as _));
-        self.glfw.window_hint(WindowHint::OpenGlProfile(OpenGlProfileHint::Core));
-        self.glfw.window_hint(WindowHint::OpenGlForwardCompat(true));
+        self.apply_window_hints(&config);
+
+        let (mut window, receiver) = match (config.fullscreen, config.monitor) {
+            (true, None) => {
+                let (width, height, refresh_rate) = self.primary_video_mode()?;
+                self.glfw.window_hint(WindowHint::RefreshRate(Some(refresh_rate)));
+                self.glfw.with_primary_monitor(|glfw, monitor| {
+                    glfw.create_window(width, height, config.title, glfw::WindowMode::FullScreen(monitor?))
+                })?
+            },
+            (true, Some(index)) => self.glfw.with_connected_monitors(|glfw, monitors| {
+                let Some(monitor) = monitors.get(index) else {
+                    log::error!("Monitor {} doesn't exist, {} are connected", index, monitors.len());
+                    return None;
+                };
+
+                let mode = monitor.get_video_mode()?;
+                glfw.window_hint(WindowHint::RefreshRate(Some(mode.refresh_rate)));
+                glfw.create_window(mode.width, mode.height, config.title, glfw::WindowMode::FullScreen(monitor))
+            })?,
+            (false, _) => self.glfw.create_window(config.width, config.height, config.title, glfw::WindowMode::Windowed)?,
+        };
+        self.initialise_window(&mut window, &config);
+
+        if !self.meets_requested_version(&window, &config) {
+            return None;
+        }
+
+        Some((window, receiver))
+    }
+
+    /// Creates a window whose context shares objects with the context of 'primary'
+    ///
+    /// Data objects (buffers, textures, shaders and programs) are shared, whereas container
+    /// objects (vertex arrays, framebuffers and program pipelines) are not and must be created
+    /// per context.
+    ///
+    /// Context rules: the new window's context is left current. GL calls always act on
+    /// the current context, so call 'make_current' on a window before issuing calls for
+    /// it. A context can only be current on one thread at a time.
+    #[allow(dead_code)]
+    pub fn create_shared_window(&mut self, config: WindowConfig, primary: &PWindow) -> Option<(PWindow, GlfwReceiver<(f64, WindowEvent)>)> {
+        self.apply_window_hints(&config);
+
+        let (mut window, receiver) =
+            primary.create_shared(config.width, config.height, config.title, glfw::WindowMode::Windowed)?;
+        self.initialise_window(&mut window, &config);
+
+        if !self.meets_requested_version(&window, &config) {
+            return None;
+        }
+
+        Some((window, receiver))
+    }
+
+    fn apply_window_hints(&mut self, config: &WindowConfig) {
+        let context_version = config.context_version.unwrap_or(self.version);
+        self.glfw.window_hint(WindowHint::ContextVersion(context_version.major as _, context_version.minor as _));
+        self.glfw.window_hint(WindowHint::OpenGlProfile(config.profile.into()));
+        self.glfw.window_hint(WindowHint::OpenGlForwardCompat(config.forward_compat));
+        self.glfw.window_hint(WindowHint::Samples(Some(config.samples)));
+        self.glfw.window_hint(WindowHint::RefreshRate(None));
+        self.glfw.window_hint(WindowHint::DoubleBuffer(config.double_buffer));
+
+        if config.lose_context_on_reset {
+            self.glfw.window_hint(WindowHint::ContextRobustness(glfw::ContextRobustnessHint::LoseContextOnReset));
+        }
 
         if !config.visible {
             self.glfw.window_hint(WindowHint::Visible(false));
         }
 
+        // 'ScaleToMonitor' applies to Windows and X11, on Mac the equivalent is the retina framebuffer
+        self.glfw.window_hint(WindowHint::ScaleToMonitor(config.scale_to_monitor));
+        self.glfw.window_hint(WindowHint::CocoaRetinaFramebuffer(config.scale_to_monitor));
+
         let validation_mode = avocet::validation::validation_mode();
         if  validation_mode == ValidationMode::Advanced ||
-            (validation_mode == ValidationMode::Dynamic && self.version.supports_debug_message_log()) {
+            (validation_mode == ValidationMode::Dynamic && context_version.supports_debug_message_log()) {
             self.glfw.window_hint(WindowHint::OpenGlDebugContext(true));
         }
+    }
 
-        let (mut window, receiver) = 
-            self.glfw.create_window(config.width, config.height, config.title, glfw::WindowMode::Windowed)?;
+    fn initialise_window(&mut self, window: &mut PWindow, config: &WindowConfig) {
         window.make_current(); // glfwMakeContextCurrent
+        avocet::validation::set_context_current(true);
+
+        // The swap interval applies to the current context
+        self.glfw.set_swap_interval(config.swap_interval);
 
-        // Load OpenGL functions
-        gl::load_with(|symbol_name| window.get_proc_address(symbol_name));
+        // Load OpenGL functions - every window is created with the same hints, so the
+        // function pointers from the first window are valid for all subsequent windows
+        if !self.functions_loaded {
+            match avocet::init(|symbol_name| window.get_proc_address(symbol_name)) {
+                Ok(()) => self.functions_loaded = true,
+                Err(error) => log::error!("Unable to load OpenGL functions: {}", error),
+            }
+        }
 
         WindowManager::initialise_debug();
 
-        Some((window, receiver))
+        if let Some(icon_path) = &config.icon {
+            match load_icon(icon_path) {
+                Ok(icon) => window.set_icon_from_pixels(vec![icon]),
+                Err(error) => log::warn!("Unable to load window icon {:?}: {}", icon_path, error),
+            }
+        }
+
+        if let Some((numerator, denominator)) = config.aspect_ratio {
+            window.set_aspect_ratio(numerator, denominator);
+        }
+
+        // The viewport must match the framebuffer size (in pixels), which on
+        // HiDPI displays is larger than the requested window size
+        let (width, height) = window.get_framebuffer_size();
+        ag::set_viewport(0, 0, width, height);
+        window.set_framebuffer_size_polling(true);
+    }
+
+    /// The version hint is a minimum, drivers may create a later (compatible) version
+    fn meets_requested_version(&self, window: &PWindow, config: &WindowConfig) -> bool {
+        let Some(requested) = config.context_version else {
+            return true;
+        };
+
+        let created = window.get_context_version();
+        let created = version::OpenGLVersion::new(created.major as _, created.minor as _);
+        if created < requested {
+            log::error!("Requested an OpenGL {} context but {} was created", requested, created);
+            false
+        } else {
+            true
+        }
+    }
+
+    /// The current video mode of the primary monitor as (width, height, refresh rate in Hz)
+    ///
+    /// With multiple monitors this is always the primary one, as chosen by the operating
+    /// system (e.g. the one with the taskbar or menu bar). Returns 'None' without a monitor.
+    pub fn primary_video_mode(&mut self) -> Option<(u32, u32, u32)> {
+        self.glfw.with_primary_monitor(|_, monitor| {
+            monitor?.get_video_mode().map(|mode| (mode.width, mode.height, mode.refresh_rate))
+        })
+    }
+
+    /// Every connected monitor, the primary one first - 'glfwGetMonitors'
+    ///
+    /// The primary monitor is chosen by the operating system, e.g. the one with the taskbar or
+    /// menu bar. Pass an index into this to 'WindowConfig::monitor' to go fullscreen on that monitor.
+    /// GLFW can't choose between GPUs, on hybrid graphics laptops that's up to the driver's settings
+    pub fn available_monitors(&mut self) -> Vec<MonitorInfo> {
+        self.glfw.with_connected_monitors(|_, monitors| {
+            monitors.iter().enumerate().map(|(index, monitor)| {
+                let (width, height, refresh_rate) = monitor.get_video_mode()
+                    .map_or((0, 0, 0), |mode| (mode.width, mode.height, mode.refresh_rate));
+
+                MonitorInfo {
+                    name: monitor.get_name().unwrap_or_default(),
+                    width,
+                    height,
+                    refresh_rate,
+                    position: monitor.get_pos(),
+                    // GLFW always lists the primary monitor first
+                    is_primary: index == 0,
+                }
+            }).collect()
+        })
+    }
+
+    /// The ratio between the framebuffer size (pixels) and the window size (screen coordinates)
+    ///
+    /// This is 1.0 on standard displays and typically 2.0 on HiDPI/retina displays
+    #[allow(dead_code)]
+    pub fn content_scale(window: &PWindow) -> (f32, f32) {
+        window.get_content_scale()
+    }
+
+    /// Changes how many vertical blanks 'swap_buffers' waits for - 'glfwSwapInterval'
+    ///
+    /// Applies to the current context and can be changed at any time, e.g. 'SwapInterval::None'
+    /// for an uncapped frame rate while benchmarking. See 'WindowConfig::swap_interval'
+    pub fn set_swap_interval(&mut self, interval: SwapInterval) {
+        self.glfw.set_swap_interval(interval);
+    }
+
+    /// Seconds since GLFW was initialised - 'glfwGetTime'
+    pub fn time(&self) -> f64 {
+        self.glfw.get_time()
     }
 
     pub fn poll_events(&mut self) {
@@ -83,11 +383,41 @@ impl WindowManager {
         if (flags as u32 & gl::CONTEXT_FLAG_DEBUG_BIT) != 0 {
             unsafe{ gl::Enable(gl::DEBUG_OUTPUT); }
             unsafe{ gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS); }
-            unsafe{ gl::DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE, gl::DONT_CARE, 0, std::ptr::null(), gl::TRUE); }
+            avocet::validation::set_debug_filter(None, None, None, true);
         }
     }
 }
 
+/// Makes a window's context current for the guard's lifetime, restoring whichever context
+/// was current before (if any) when dropped
+///
+/// Use it to create resources for a specific window without disturbing the caller, e.g.
+/// '{ let _guard = ContextGuard::new(&mut secondary); ... }'. The previous context's window
+/// must outlive the guard. Contexts are bound to a thread, so the guard can't be sent to another.
+#[allow(dead_code)]
+pub struct ContextGuard {
+    previous: *mut glfw::ffi::GLFWwindow,
+}
+
+#[allow(dead_code)]
+impl ContextGuard {
+    pub fn new(window: &mut PWindow) -> Self {
+        // glfw-rs has no safe way to query the current context, which may belong to any window
+        let previous = unsafe{ glfw::ffi::glfwGetCurrentContext() };
+        window.make_current();
+        avocet::validation::set_context_current(true);
+
+        Self { previous }
+    }
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        unsafe{ glfw::ffi::glfwMakeContextCurrent(self.previous) };
+        avocet::validation::set_context_current(!self.previous.is_null());
+    }
+}
+
 fn find_opengl_version(glfw: &mut Glfw) -> Option<version::OpenGLVersion> {
     // When looking into how GLFW works - when requesting a specific context version
     // it will lock-in on the requested version. Ideally we want the highest version
@@ -107,11 +437,40 @@ fn find_opengl_version(glfw: &mut Glfw) -> Option<version::OpenGLVersion> {
 
     if let Some((mut window, _)) = glfw.create_window(1, 1, "", glfw::WindowMode::Windowed) {
         window.make_current();
-        gl::load_with(|symbol_name| window.get_proc_address(symbol_name));
+        avocet::init(|symbol_name| window.get_proc_address(symbol_name)).ok()?;
         glfw.default_window_hints();
 
-        Some(version::get_opengl_version())
+        let version = version::get_opengl_version();
+
+        // Destroying the probe window leaves no context current
+        drop(window);
+        avocet::validation::set_context_current(false);
+
+        Some(version)
     } else {
         None
     }
+}
+
+/// Loads an image (typically a PNG) as a window icon, see 'ag::decode_image' for the formats
+fn load_icon(path: &Path) -> Result<PixelImage, String> {
+    let bytes = std::fs::read(path).map_err(|error| error.to_string())?;
+    let image = ag::decode_image(&bytes).map_err(|error| error.to_string())?;
+
+    // GLFW expects RGBA, so expand greyscale and add opaque alpha where it's missing
+    let channels = image.format.channels() as usize;
+    let pixels = image.data.chunks_exact(channels)
+        .map(|texel| {
+            let [r, g, b, a] = match *texel {
+                [luma] => [luma, luma, luma, u8::MAX],
+                [luma, alpha] => [luma, luma, luma, alpha],
+                [r, g, b] => [r, g, b, u8::MAX],
+                [r, g, b, a] => [r, g, b, a],
+                _ => unreachable!("Textures have between 1 and 4 channels"),
+            };
+            u32::from_ne_bytes([r, g, b, a])
+        })
+        .collect();
+
+    Ok(PixelImage { width: image.width, height: image.height, pixels })
 }
\ No newline at end of file